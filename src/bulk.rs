@@ -0,0 +1,210 @@
+//! Bulk changes: one action applied to every check matched by a selector, e.g. to disable the
+//! checks of a service during a maintenance window.
+
+use crate::client::Client;
+use crate::manifest::name;
+use crate::messages::check::{Check, CheckParams, MuteUntil, Period};
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+use crate::resolve::glob;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+#[cfg(feature = "cli")]
+use clap::ArgMatches;
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+
+/// Selector picks checks: a check is selected when it matches every criterion given.
+#[derive(Clone, Debug, Default)]
+pub struct Selector {
+    /// Selects every check; needed when no other criterion is given
+    pub all: bool,
+    /// A pattern for the alias, with `*` for any characters and `?` for one
+    pub alias_glob: Option<String>,
+    pub url_regex: Option<Regex>,
+    pub down: bool,
+    pub disabled: bool,
+    pub period: Option<Period>,
+}
+
+impl Selector {
+    #[cfg(feature = "cli")]
+    pub fn parse(matches: &ArgMatches<'_>) -> Result<Selector, UpdownError> {
+        let mut parser = Parser::new(matches);
+        let mut selector = Selector {
+            all: matches.is_present("all"),
+            alias_glob: matches.value_of("alias-glob").map(str::to_string),
+            url_regex: parser.parse_value("url-regex"),
+            down: matches.is_present("down"),
+            disabled: matches.is_present("disabled"),
+            period: None,
+        };
+        let period = parser.parse_value("period");
+        parser.finish()?;
+        if let Some(period) = period {
+            selector.period = Some(Period::validated(period)?);
+        }
+        if !selector.all && selector.is_empty() {
+            return Err(UpdownError::BadArg(
+                "select checks with --alias-glob, --url-regex, --down, --disabled or --period, \
+                 or --all for every check"
+                    .to_string(),
+            ));
+        }
+        Ok(selector)
+    }
+
+    /// Whether no criterion is given.
+    pub fn is_empty(&self) -> bool {
+        self.alias_glob.is_none()
+            && self.url_regex.is_none()
+            && !self.down
+            && !self.disabled
+            && self.period.is_none()
+    }
+
+    pub fn matches(&self, check: &Check) -> bool {
+        let alias = check.alias.as_deref();
+        let url = check.url.as_deref();
+        (self.all || !self.is_empty())
+            && self
+                .alias_glob
+                .as_ref()
+                .is_none_or(|pattern| alias.is_some_and(|a| glob(pattern, a)))
+            && self
+                .url_regex
+                .as_ref()
+                .is_none_or(|regex| url.is_some_and(|u| regex.is_match(u)))
+            && (!self.down || check.down == Some(true))
+            && (!self.disabled || check.enabled == Some(false))
+            && self
+                .period
+                .is_none_or(|period| check.period == Some(period))
+    }
+}
+
+/// BulkAction is the change made to each selected check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BulkAction {
+    Enable,
+    Disable,
+    Mute(MuteUntil),
+    Unmute,
+    SetPeriod(Period),
+    Delete,
+}
+
+impl BulkAction {
+    /// Parses the action subcommand of `updown bulk`.
+    #[cfg(feature = "cli")]
+    pub fn parse(action: &str, matches: &ArgMatches<'_>) -> Result<BulkAction, UpdownError> {
+        let mut parser = Parser::new(matches);
+        let action = match action {
+            "enable" => BulkAction::Enable,
+            "disable" => BulkAction::Disable,
+            "mute" => BulkAction::Mute(parser.parse_value("until").unwrap_or(MuteUntil::Forever)),
+            "unmute" => BulkAction::Unmute,
+            "set-period" => {
+                let seconds = parser.parse_value("seconds").unwrap_or_default();
+                BulkAction::SetPeriod(Period::validated(seconds)?)
+            }
+            "delete" => BulkAction::Delete,
+            action => {
+                return Err(UpdownError::BadArg(format!(
+                    "unknown bulk action {}",
+                    action
+                )))
+            }
+        };
+        parser.finish()?;
+        Ok(action)
+    }
+
+    /// The update request making the change, or none for a deletion.
+    fn params(self, token: String) -> Option<CheckParams> {
+        let mut params = CheckParams {
+            token,
+            ..Default::default()
+        };
+        match self {
+            BulkAction::Enable => params.enabled = Some(true),
+            BulkAction::Disable => params.enabled = Some(false),
+            BulkAction::Mute(until) => params.mute_until = Some(until),
+            BulkAction::Unmute => params.mute_until = Some(MuteUntil::Unmuted),
+            BulkAction::SetPeriod(period) => params.period = Some(period),
+            BulkAction::Delete => return None,
+        }
+        Some(params)
+    }
+}
+
+impl fmt::Display for BulkAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BulkAction::Enable => f.write_str("enable"),
+            BulkAction::Disable => f.write_str("disable"),
+            BulkAction::Mute(_) => f.write_str("mute"),
+            BulkAction::Unmute => f.write_str("unmute"),
+            BulkAction::SetPeriod(period) => write!(f, "set the period to {}s for", period),
+            BulkAction::Delete => f.write_str("delete"),
+        }
+    }
+}
+
+/// Outcome is the result of the action on one check.
+#[derive(Clone, Debug, Serialize)]
+pub struct Outcome {
+    pub token: String,
+    /// The alias or URL of the check
+    pub check: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Applies `action` to `checks`, `concurrency` requests at a time. `progress` is called as each
+/// request completes, with the number completed so far. The outcomes are in the order of
+/// `checks`, whether the requests succeeded or not.
+pub async fn run(
+    client: &Client,
+    action: BulkAction,
+    checks: &[Check],
+    concurrency: usize,
+    mut progress: impl FnMut(usize, &Outcome),
+) -> Vec<Outcome> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    for (index, check) in checks.iter().enumerate() {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let sender = sender.clone();
+        let token = check.token.clone().unwrap_or_default();
+        let check = name(check);
+        tokio::spawn(async move {
+            let _permit = semaphore.acquire().await;
+            let result = match action.params(token.clone()) {
+                Some(params) => client.update(&params).await.map(|_| ()),
+                None => client.delete(&token).await.map(|_| ()),
+            };
+            let outcome = Outcome {
+                token,
+                check,
+                error: result.err().map(|e| e.to_string()),
+            };
+            // The receiver only goes away once every outcome is in
+            sender.send((index, outcome)).ok();
+        });
+    }
+    drop(sender);
+
+    let mut outcomes: Vec<Option<Outcome>> = vec![None; checks.len()];
+    let mut done = 0;
+    while let Some((index, outcome)) = receiver.recv().await {
+        done += 1;
+        progress(done, &outcome);
+        outcomes[index] = Some(outcome);
+    }
+    outcomes.into_iter().flatten().collect()
+}
@@ -1,122 +1,439 @@
 use core::result::Result::Ok;
 
-use std::collections::HashMap;
+use std::env;
 
-use crate::messages::check::{Check, CheckParams, Checks};
-use crate::messages::downtime::{DowntimeParams, Downtimes};
+use crate::config::Config;
+use crate::messages::check::{Check, CheckParams};
+use crate::messages::downtime::{Downtime, DowntimeParams};
 use crate::messages::metric::{Metrics, MetricsParams};
-use crate::messages::MessageError;
-use crate::CHECKS_URL;
-use reqwest::Url;
+use crate::messages::node::Nodes;
+use crate::messages::pulse::PulseParams;
+use crate::messages::recipient::{Recipient, RecipientParams};
+use crate::messages::status_page::{StatusPage, StatusPageParams};
+use crate::messages::webhook::{Webhook, WebhookParams};
+use crate::messages::{Deleted, ErrorMessage};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::{ApiError, UpdownError};
+use reqwest::{Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use tokio::time::delay_for;
+
+/// The updown.io API is served under this URL unless another base URL is configured.
+pub const DEFAULT_BASE_URL: &str = "https://updown.io";
+
+/// Environment variable that overrides the configured base URL.
+pub const BASE_URL_ENV: &str = "UPDOWN_BASE_URL";
+
+/// Sent as the User-Agent when none is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!("updown-rust/", env!("CARGO_PKG_VERSION"));
+
+/// The API key is sent in this header rather than in the query string, so it stays out of logs.
+const API_KEY_HEADER: &str = "X-API-KEY";
 
 /// Client is the API entry point.
-/// A new Client instance will hold references to the user's full(?) and read-only API keys.
-/// The read-only key is used internally for GET requests, the full key for POST and PUT requests.
-/// The implementation defines methods that are used for the
-pub(crate) struct Client<'a> {
-    pub(crate) api_key: &'a str,
-    read_only_api_key: &'a str,
-    user_agent: &'a str,
+/// A new Client instance will hold the user's full and/or read-only API keys.
+/// The read-only key, when there is one, is used for GET requests; the full key is required for
+/// POST, PUT and DELETE requests, so a client with only a read-only key refuses to send them.
+/// The implementation defines one method per updown API request. All requests share one
+/// underlying HTTP client, so connections are reused.
+/// Cloning a client is cheap: clones share the HTTP client and the [`RateLimiter`], if any, so
+/// tasks spawned with their own clone stay within one request budget.
+///
+/// ```no_run
+/// use updown::messages::metric::MetricsParamsBuilder;
+/// use updown::Client;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), updown::UpdownError> {
+/// let client = Client::new("your-api-key", "your-read-only-api-key", "your-user-agent")?;
+/// let params = MetricsParamsBuilder::default()
+///     .token("your-token")
+///     .group("time")
+///     .build()
+///     .unwrap();
+/// let metrics = client.metrics(&params).await?;
+/// println!("{:?}", metrics);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Requests go to [`DEFAULT_BASE_URL`] unless a base URL is given to the [`ClientBuilder`], the
+/// [`Config`] or the `UPDOWN_BASE_URL` environment variable, e.g. to target a local stub server:
+///
+/// ```
+/// use updown::client::ClientBuilder;
+///
+/// let client = ClientBuilder::default()
+///     .read_only_api_key("your-read-only-api-key")
+///     .base_url("http://localhost:8080")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Builder, Clone)]
+#[builder(setter(into, strip_option), build_fn(skip))]
+pub struct Client {
+    api_key: Option<String>,
+    read_only_api_key: Option<String>,
+    user_agent: Option<String>,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    #[builder(setter(skip))]
     http_client: reqwest::Client,
 }
 
-///
-impl Client<'_> {
+impl ClientBuilder {
+    /// Builds the client. The base URL falls back to `UPDOWN_BASE_URL`, then to [`DEFAULT_BASE_URL`],
+    /// the user agent to [`DEFAULT_USER_AGENT`] and the retry policy to [`RetryPolicy::default`].
+    /// Requests are not rate limited unless a [`RateLimiter`] is given.
+    pub fn build(&self) -> Result<Client, UpdownError> {
+        let base_url = match &self.base_url {
+            Some(url) => url.clone(),
+            None => default_base_url(),
+        };
+        // Fail early on a malformed base URL rather than on the first request
+        Url::parse(&base_url)?;
+
+        let user_agent = non_empty(self.user_agent.clone().flatten());
+        let http_client = reqwest::Client::builder()
+            .user_agent(user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+            .build()?;
 
-    pub async fn all(&self) -> Result<Checks, MessageError> {
-        let url = Url::parse((CHECKS_URL.to_owned()).as_str()).unwrap();
+        Ok(Client {
+            api_key: non_empty(self.api_key.clone().flatten()),
+            read_only_api_key: non_empty(self.read_only_api_key.clone().flatten()),
+            user_agent,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            retry_policy: self.retry_policy.clone().unwrap_or_default(),
+            rate_limiter: self.rate_limiter.clone().flatten(),
+            http_client,
+        })
+    }
+}
+
+impl Client {
+    /// GET /api/checks
+    pub async fn all(&self) -> Result<Vec<Check>, UpdownError> {
+        let url = self.url(&["checks"])?;
+        let resp = self.send(self.request(Method::GET, url)?).await?;
+        parse(resp).await
+    }
+
+    /// GET /api/checks/:token, optionally including metrics
+    pub async fn check(&self, token: &str, metrics: bool) -> Result<Check, UpdownError> {
+        let url = self.url(&["checks", token])?;
+        let mut request = self.request(Method::GET, url)?;
+        if metrics {
+            request = request.query(&[("metrics", "true")]);
+        }
+        parse(self.send(request).await?).await
+    }
+
+    /// GET /api/checks/:token/downtimes
+    pub async fn downtimes(
+        &self,
+        params: &DowntimeParams<'_>,
+    ) -> Result<Vec<Downtime>, UpdownError> {
+        let url = self.url(&["checks", params.token, "downtimes"])?;
         let resp = self
-            .http_client
-            .get(url)
-            .query(&[("api-key", self.api_key)])
-            .send()
-            .await?
-            .json()
+            .send(self.request(Method::GET, url)?.query(&params))
             .await?;
-        Ok(resp)
+        parse(resp).await
     }
 
-    pub async fn check(&self, token: &str, metrics: bool) -> Result<Check, MessageError> {
-        // TODO build the request parameters in a separate function
-        let mut params: HashMap<&str, &str> = HashMap::new();
-        params.insert("api-key", self.api_key);
-        if metrics {
-            params.insert("metrics", "true");
+    /// GET /api/checks/:token/metrics
+    pub async fn metrics(&self, params: &MetricsParams<'_>) -> Result<Metrics, UpdownError> {
+        let url = self.url(&["checks", params.token, "metrics"])?;
+        let resp = self
+            .send(self.request(Method::GET, url)?.query(&params))
+            .await?;
+        parse(resp).await
+    }
+
+    /// POST /api/checks
+    pub async fn create(&self, params: &CheckParams) -> Result<Check, UpdownError> {
+        let url = self.url(&["checks"])?;
+        let resp = self
+            .send(self.request(Method::POST, url)?.json(&params))
+            .await?;
+        parse(resp).await
+    }
+
+    /// PUT /api/checks/:token
+    pub async fn update(&self, params: &CheckParams) -> Result<Check, UpdownError> {
+        let url = self.url(&["checks", &params.token])?;
+        let resp = self
+            .send(self.request(Method::PUT, url)?.json(&params))
+            .await?;
+        parse(resp).await
+    }
+
+    /// DELETE /api/checks/:token
+    pub async fn delete(&self, token: &str) -> Result<Deleted, UpdownError> {
+        let url = self.url(&["checks", token])?;
+        let resp = self.send(self.request(Method::DELETE, url)?).await?;
+        parse(resp).await
+    }
+
+    /// Pings the URL of a pulse check (see [`Check::pulse_url`]), e.g. at the end of a cron job.
+    /// The URL identifies the check, so no API key is sent.
+    pub async fn pulse(&self, pulse_url: &str, params: &PulseParams) -> Result<(), UpdownError> {
+        let url = Url::parse(pulse_url)?;
+        let resp = self.send(self.http_client.post(url).json(&params)).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(api_error(resp).await)
         }
-        let url =
-            Url::parse_with_params((CHECKS_URL.to_owned() + "/" + token).as_str(), params).unwrap();
-        let resp = reqwest::get(url).await?.json().await?;
-        Ok(resp)
     }
 
-    pub async fn downtimes(&self, params: &DowntimeParams<'_>) -> Result<Downtimes, MessageError> {
-        // -> Result<HashMap<String, Downtime>, MessageError>{
-        let url = Url::parse((CHECKS_URL.to_owned() + "/" + params.token + "/downtimes").as_str())
-            .unwrap();
+    /// GET /api/nodes
+    pub async fn nodes(&self) -> Result<Nodes, UpdownError> {
+        let url = self.url(&["nodes"])?;
+        parse(self.send(self.request(Method::GET, url)?).await?).await
+    }
+
+    /// GET /api/nodes/ipv4
+    pub async fn nodes_ipv4(&self) -> Result<Vec<String>, UpdownError> {
+        let url = self.url(&["nodes", "ipv4"])?;
+        parse(self.send(self.request(Method::GET, url)?).await?).await
+    }
+
+    /// GET /api/nodes/ipv6
+    pub async fn nodes_ipv6(&self) -> Result<Vec<String>, UpdownError> {
+        let url = self.url(&["nodes", "ipv6"])?;
+        parse(self.send(self.request(Method::GET, url)?).await?).await
+    }
+
+    /// GET /api/recipients
+    pub async fn recipients(&self) -> Result<Vec<Recipient>, UpdownError> {
+        let url = self.url(&["recipients"])?;
+        parse(self.send(self.request(Method::GET, url)?).await?).await
+    }
+
+    /// POST /api/recipients
+    pub async fn create_recipient(
+        &self,
+        params: &RecipientParams,
+    ) -> Result<Recipient, UpdownError> {
+        let url = self.url(&["recipients"])?;
         let resp = self
-            .http_client
-            .get(url)
-            .query(&params)
-            .send()
-            .await?
-            .json()
+            .send(self.request(Method::POST, url)?.json(&params))
             .await?;
-        Ok(resp)
+        parse(resp).await
+    }
+
+    /// DELETE /api/recipients/:id
+    pub async fn delete_recipient(&self, id: &str) -> Result<Deleted, UpdownError> {
+        let url = self.url(&["recipients", id])?;
+        parse(self.send(self.request(Method::DELETE, url)?).await?).await
     }
 
-    pub async fn metrics(&self, params: &MetricsParams<'_>) -> Result<Metrics, MessageError> {
-        // -> Result<HashMap<String, Downtime>, MessageError>{
-        let url =
-            Url::parse((CHECKS_URL.to_owned() + "/" + params.token + "/metrics").as_str()).unwrap();
+    /// GET /api/status_pages
+    pub async fn status_pages(&self) -> Result<Vec<StatusPage>, UpdownError> {
+        let url = self.url(&["status_pages"])?;
+        parse(self.send(self.request(Method::GET, url)?).await?).await
+    }
+
+    /// POST /api/status_pages
+    pub async fn create_status_page(
+        &self,
+        params: &StatusPageParams,
+    ) -> Result<StatusPage, UpdownError> {
+        let url = self.url(&["status_pages"])?;
         let resp = self
-            .http_client
-            .get(url)
-            .query(&params)
-            .send()
-            .await?
-            .json()
+            .send(self.request(Method::POST, url)?.json(&params))
             .await?;
-        Ok(resp)
+        parse(resp).await
     }
 
-    pub async fn update(&self, params: &CheckParams) -> Result<Check, MessageError> {
-        let url =
-            Url::parse((CHECKS_URL.to_owned() + "/" + &params.token.as_str()).as_str()).unwrap();
+    /// PUT /api/status_pages/:token
+    pub async fn update_status_page(
+        &self,
+        params: &StatusPageParams,
+    ) -> Result<StatusPage, UpdownError> {
+        let url = self.url(&["status_pages", &params.token])?;
         let resp = self
-            .http_client
-            .put(url)
-            .json(&params)
-            .send()
-            .await?
-            .json()
+            .send(self.request(Method::PUT, url)?.json(&params))
             .await?;
-        Ok(resp)
+        parse(resp).await
+    }
+
+    /// DELETE /api/status_pages/:token
+    pub async fn delete_status_page(&self, token: &str) -> Result<Deleted, UpdownError> {
+        let url = self.url(&["status_pages", token])?;
+        parse(self.send(self.request(Method::DELETE, url)?).await?).await
     }
 
-    pub async fn delete(&self, token: &str) -> Result<HashMap<String, String>, MessageError> {
-        let url = Url::parse((CHECKS_URL.to_owned() + "/" + token).as_str()).unwrap();
+    /// GET /api/webhooks
+    pub async fn webhooks(&self) -> Result<Vec<Webhook>, UpdownError> {
+        let url = self.url(&["webhooks"])?;
+        parse(self.send(self.request(Method::GET, url)?).await?).await
+    }
+
+    /// POST /api/webhooks
+    pub async fn create_webhook(&self, params: &WebhookParams) -> Result<Webhook, UpdownError> {
+        let url = self.url(&["webhooks"])?;
         let resp = self
-            .http_client
-            .delete(url)
-            .query(&[("api-key", self.api_key)])
-            .send()
-            .await?
-            .json()
+            .send(self.request(Method::POST, url)?.json(&params))
             .await?;
-        Ok(resp)
-    }
-
-    pub fn new<'a>(
-        api_key: &'a str,
-        private_api_key: &'a str,
-        user_agent: &'a str,
-    ) -> Client <'a> {
-        let client = Client {
-            api_key,
-            read_only_api_key: private_api_key,
-            user_agent: user_agent,
-            http_client: Default::default(),
+        parse(resp).await
+    }
+
+    /// DELETE /api/webhooks/:id
+    pub async fn delete_webhook(&self, id: &str) -> Result<Deleted, UpdownError> {
+        let url = self.url(&["webhooks", id])?;
+        parse(self.send(self.request(Method::DELETE, url)?).await?).await
+    }
+
+    /// Creates a client from the full API key, the read-only API key and a user agent. Empty values
+    /// are treated as missing.
+    pub fn new(
+        api_key: &str,
+        read_only_api_key: &str,
+        user_agent: &str,
+    ) -> Result<Client, UpdownError> {
+        ClientBuilder::default()
+            .api_key(api_key)
+            .read_only_api_key(read_only_api_key)
+            .user_agent(user_agent)
+            .build()
+    }
+
+    /// Creates a client from a [`Config`], using its base URL and rate limit if they are set.
+    pub fn from_config(config: &Config) -> Result<Client, UpdownError> {
+        let mut builder = ClientBuilder::default();
+        builder
+            .api_key(config.api_key.as_str())
+            .read_only_api_key(config.read_only_api_key.as_str())
+            .user_agent(config.user_agent.as_str());
+        if let Some(base_url) = &config.base_url {
+            builder.base_url(base_url.as_str());
+        }
+        if let Some(rate_limiter) = config.rate_limiter()? {
+            builder.rate_limiter(rate_limiter);
+        }
+        builder.build()
+    }
+
+    /// The base URL requests are sent to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The configured user agent, if any.
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    /// Starts a request authenticated with the key suited to its method.
+    fn request(&self, method: Method, url: Url) -> Result<RequestBuilder, UpdownError> {
+        let key = self.key_for(&method)?;
+        Ok(self
+            .http_client
+            .request(method, url)
+            .header(API_KEY_HEADER, key))
+    }
+
+    /// Sends a request, retrying it as the retry policy allows. Every attempt waits for the rate
+    /// limiter.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, UpdownError> {
+        let request = request.build()?;
+        let retries = self.retry_policy.applies_to(request.method());
+        let mut attempt = 1;
+        loop {
+            // Bodies are always buffered JSON, so requests can be cloned
+            let attempt_request = request.try_clone().expect("request body is not a stream");
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            let result = self.http_client.execute(attempt_request).await;
+            let delay = match &result {
+                _ if !retries || attempt >= self.retry_policy.max_attempts => None,
+                Ok(resp) => self.retry_policy.delay_after_response(resp, attempt),
+                Err(e) => self.retry_policy.delay_after_error(e, attempt),
+            };
+            match delay {
+                Some(delay) => {
+                    delay_for(delay).await;
+                    attempt += 1;
+                }
+                None => return Ok(result?),
+            }
+        }
+    }
+
+    /// GET requests use the read-only key if there is one, other requests need the full key.
+    fn key_for(&self, method: &Method) -> Result<&str, UpdownError> {
+        let key = if *method == Method::GET {
+            self.read_only_api_key.as_ref().or(self.api_key.as_ref())
+        } else {
+            self.api_key.as_ref()
         };
-        client
+        key.map(String::as_str).ok_or_else(|| {
+            UpdownError::Unauthorized(if self.read_only_api_key.is_some() {
+                format!(
+                    "{} requests need the full API key, not the read-only one",
+                    method
+                )
+            } else {
+                "no API key".to_string()
+            })
+        })
+    }
+
+    /// Builds the URL of an API endpoint, e.g. `checks/:token` -> `<base_url>/api/checks/:token`
+    /// The URL of an API endpoint, e.g. `["checks", token]` for `/api/checks/:token`. Tokens and
+    /// ids are escaped, so that none of them can reach another endpoint.
+    fn url(&self, segments: &[&str]) -> Result<Url, UpdownError> {
+        if let Some(segment) = segments.iter().find(|s| matches!(**s, "" | "." | "..")) {
+            return Err(UpdownError::BadArg(format!(
+                "{:?} is not a valid token or id",
+                segment
+            )));
+        }
+        let mut url = Url::parse(&format!("{}/api", self.base_url))?;
+        url.path_segments_mut()
+            .map_err(|_| UpdownError::UrlFailed(url::ParseError::RelativeUrlWithCannotBeABaseBase))?
+            .extend(segments);
+        Ok(url)
+    }
+}
+
+/// Deserializes a successful response, or turns an error response into an [`ApiError`].
+async fn parse<T: DeserializeOwned>(resp: Response) -> Result<T, UpdownError> {
+    if resp.status().is_success() {
+        Ok(resp.json().await?)
+    } else {
+        Err(api_error(resp).await)
     }
 }
+
+/// Turns an error response into an [`ApiError`], with the message sent by the API if any.
+async fn api_error(resp: Response) -> UpdownError {
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<ErrorMessage>(&body)
+        .ok()
+        .and_then(|e| e.error)
+        .unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_string()
+        });
+    ApiError {
+        status: status.as_u16(),
+        message,
+    }
+    .into()
+}
+
+fn non_empty(key: Option<String>) -> Option<String> {
+    key.filter(|k| !k.is_empty())
+}
+
+fn default_base_url() -> String {
+    env::var(BASE_URL_ENV).unwrap_or_else(|_| DEFAULT_BASE_URL.to_string())
+}
@@ -1,42 +1,79 @@
-use crate::client::Client;
-use crate::config::Config;
-use crate::messages::check::{Check, CheckParams};
-use crate::messages::downtime::{DowntimeParams, Downtimes};
-use crate::messages::metric::{Metrics, MetricsParams};
-use crate::messages::MessageError;
 use clap::ArgMatches;
-use serde::export::fmt::Display;
 use std::fmt::Debug;
-use std::str::FromStr;
+use std::future::Future;
+use std::path::Path;
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
+use updown::bulk::{self, BulkAction, Outcome, Selector};
+use updown::client::Client;
+use updown::config::{Config, Profile, Settings, DEFAULT_PROFILE};
+use updown::manifest::{self, Action, Change, Manifest, ManifestFormat};
+use updown::messages::check::{Check, CheckParams, CheckType};
+use updown::messages::downtime::{Downtime, DowntimeParams};
+use updown::messages::metric::{Metrics, MetricsParams};
+use updown::messages::node::Nodes;
+use updown::messages::pulse::PulseParams;
+use updown::messages::recipient::{Recipient, RecipientParams};
+use updown::messages::status_page::{StatusPage, StatusPageParams};
+use updown::messages::webhook::{Webhook, WebhookParams};
+use updown::messages::Deleted;
+use updown::output::Format;
+use updown::parser::Parser;
+use updown::rate_limit::{MIN_REQUESTS_PER_SECOND, RATE_REASON};
+use updown::resolve;
+use updown::UpdownError;
 
+/// The structopt definitions are only used to build the clap `App`; values are read back through
+/// `ArgMatches`.
+#[allow(dead_code)]
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "updown",
     about = "A cli for http://updown.io",
     rename_all = "snake"
 )]
-pub(crate) struct Updown {
+pub struct Updown {
+    /// Shows the check with this token, alias or URL, as the check subcommand does
     #[structopt(conflicts_with("opt"))]
     token_or_url: Option<String>,
 
+    /// How responses are printed: json, json-pretty, table, yaml or csv
+    #[structopt(
+        long,
+        global = true,
+        default_value = "json",
+        possible_values = &Format::NAMES
+    )]
+    output: String,
+
+    /// The profile whose account is used, instead of UPDOWN_PROFILE or the current profile
+    #[structopt(long, global = true)]
+    profile: Option<String>,
+
     #[structopt(subcommand)]
     opt: Option<Subcommand>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, StructOpt)]
 enum Subcommand {
+    /// Stores the keys of the selected profile, or manages the profiles
+    #[structopt(
+        setting = AppSettings::SubcommandsNegateReqs,
+        setting = AppSettings::ArgsNegateSubcommands
+    )]
     Config {
-        api_key: String,
-
-        private_api_key: String,
+        #[structopt(flatten)]
+        keys: Keys,
 
-        user_agent: String,
+        #[structopt(subcommand)]
+        action: Option<ProfileAction>,
     },
 
     All {},
 
     Check {
+        /// A check token, alias or URL, or a pattern such as 'https://*.example.com/*'
         token: String,
 
         #[structopt(long)]
@@ -44,6 +81,7 @@ enum Subcommand {
     },
 
     Downtimes {
+        /// A check token, alias or URL, or a pattern such as 'https://*.example.com/*'
         token: String,
 
         #[structopt(long)]
@@ -54,12 +92,15 @@ enum Subcommand {
     },
 
     Metrics {
+        /// A check token, alias or URL, or a pattern such as 'https://*.example.com/*'
         token: String,
 
-        #[structopt(long)]
+        /// A date and time, or a time relative to now such as -7d
+        #[structopt(long, allow_hyphen_values = true)]
         from: Option<String>,
 
-        #[structopt(long)]
+        /// A date and time, or a time relative to now such as now or -1d
+        #[structopt(long, allow_hyphen_values = true)]
         to: Option<String>,
 
         #[structopt(long, possible_values=&["time", "host"])]
@@ -67,7 +108,12 @@ enum Subcommand {
     },
 
     Add {
-        url: String,
+        #[structopt(required_unless = "pulse")]
+        url: Option<String>,
+
+        /// Creates a pulse check, pinged by a cron job with `updown pulse`, instead of an HTTP check
+        #[structopt(long, conflicts_with = "url")]
+        pulse: bool,
 
         #[structopt(long)]
         alias: Option<String>,
@@ -102,8 +148,10 @@ enum Subcommand {
         #[structopt(long)]
         published: Option<bool>,
 
-        #[structopt(long)]
-        disabled_locations: Option<Vec<String>>,
+        /// The code of a location the check must not run from (see `updown nodes`), repeated
+        /// for each location
+        #[structopt(long, number_of_values = 1)]
+        disabled_location: Vec<String>,
 
         #[structopt(long)]
         last_check_at: Option<String>,
@@ -111,22 +159,38 @@ enum Subcommand {
         #[structopt(long)]
         next_check_at: Option<String>,
 
-        #[structopt(long)]
-        mute_until: Option<String>, //?
+        /// forever, a date and time, or a time relative to now such as +2h
+        #[structopt(long, allow_hyphen_values = true)]
+        mute_until: Option<String>,
 
         #[structopt(long)]
         favicon_url: Option<String>,
 
-        //#[structopt(long)]
-        //custom_headers: Option<HashMap<String, String>>,
+        /// A header sent with the check requests, as 'Name: value', repeated for each header
+        #[structopt(long, alias = "custom-header", number_of_values = 1)]
+        header: Vec<String>,
+
         #[structopt(long)]
         http_verb: Option<String>,
 
         #[structopt(long)]
         http_body: Option<String>,
+
+        /// The id of a recipient to alert, repeated for each recipient
+        #[structopt(long, number_of_values = 1)]
+        recipient: Vec<String>,
+
+        /// Prints the changes the request would make instead of making them
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// With --dry-run, prints the changes as a JSON patch rather than a diff
+        #[structopt(long, requires = "dry-run")]
+        json_patch: bool,
     },
 
     Update {
+        /// A check token, alias or URL, or a pattern such as 'https://*.example.com/*'
         token: String,
 
         #[structopt(long)]
@@ -165,8 +229,14 @@ enum Subcommand {
         #[structopt(long)]
         published: Option<bool>,
 
-        #[structopt(long)]
-        disabled_locations: Option<Vec<String>>,
+        /// The code of a location the check must not run from (see `updown nodes`), repeated
+        /// for each location
+        #[structopt(long, number_of_values = 1)]
+        disabled_location: Vec<String>,
+
+        /// Runs the check from every location again
+        #[structopt(long, conflicts_with = "disabled-location")]
+        clear_disabled_locations: bool,
 
         #[structopt(long)]
         last_check_at: Option<String>,
@@ -174,116 +244,926 @@ enum Subcommand {
         #[structopt(long)]
         next_check_at: Option<String>,
 
-        #[structopt(long)]
-        mute_until: Option<String>, //?
+        /// forever, a date and time, or a time relative to now such as +2h
+        #[structopt(long, allow_hyphen_values = true)]
+        mute_until: Option<String>,
 
         #[structopt(long)]
         favicon_url: Option<String>,
 
-        //#[structopt(long)]
-        //custom_headers: Option<HashMap<String, String>>,
+        /// A header sent with the check requests, as 'Name: value', repeated for each header;
+        /// replaces the headers sent
+        #[structopt(long, alias = "custom-header", number_of_values = 1)]
+        header: Vec<String>,
+
+        /// Removes every custom header
+        #[structopt(long, conflicts_with = "header")]
+        clear_headers: bool,
+
         #[structopt(long)]
         http_verb: Option<String>,
 
         #[structopt(long)]
         http_body: Option<String>,
+
+        /// The id of a recipient to alert, repeated for each recipient
+        #[structopt(long, number_of_values = 1)]
+        recipient: Vec<String>,
+
+        /// Prints the changes the request would make instead of making them
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// With --dry-run, prints the changes as a JSON patch rather than a diff
+        #[structopt(long, requires = "dry-run")]
+        json_patch: bool,
     },
 
     Delete {
+        /// A check token, alias or URL, or a pattern such as 'https://*.example.com/*'
         token: String,
+
+        /// Prints the changes the request would make instead of making them
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// With --dry-run, prints the changes as a JSON patch rather than a diff
+        #[structopt(long, requires = "dry-run")]
+        json_patch: bool,
+    },
+
+    /// Creates and updates checks to match a manifest of the checks the account should have
+    Apply {
+        /// A YAML, TOML or JSON file, told apart by its extension
+        manifest: String,
+
+        /// Also deletes the checks the manifest doesn't list
+        #[structopt(long)]
+        prune: bool,
+    },
+
+    /// Writes a manifest of the checks of the account, which apply leaves as they are
+    Export {
+        /// The file to write, printing the manifest when left out
+        file: Option<String>,
+
+        /// yaml, toml or json; by default told from the extension of the file, or yaml
+        #[structopt(long, possible_values = &ManifestFormat::NAMES)]
+        format: Option<String>,
+    },
+
+    /// Applies an action to every check matching the selectors, e.g. for a maintenance window
+    Bulk {
+        #[structopt(subcommand)]
+        action: BulkCommand,
+    },
+
+    /// Copies a check to the account of another profile
+    Copy {
+        /// A check token, alias or URL, or a pattern such as 'https://*.example.com/*'
+        token: String,
+
+        /// The profile to copy the check to
+        #[structopt(long)]
+        to: String,
+    },
+
+    /// Prints the changes apply would make, exiting with 1 when there are some
+    Plan {
+        /// A YAML, TOML or JSON file, told apart by its extension
+        manifest: String,
+
+        /// Also plans the deletion of the checks the manifest doesn't list
+        #[structopt(long)]
+        prune: bool,
+
+        /// Prints the changes as a JSON patch rather than a diff
+        #[structopt(long)]
+        json_patch: bool,
+    },
+
+    /// Pings a pulse check, given by token or by its pulse URL, e.g. at the end of a cron job
+    Pulse {
+        token: String,
+
+        /// The exit code of the job
+        #[structopt(long, allow_hyphen_values = true)]
+        exit_code: Option<i32>,
+
+        /// How long the job ran, in seconds
+        #[structopt(long)]
+        duration: Option<f64>,
+    },
+
+    /// Lists the monitoring locations, or with --ipv4, --ipv6 or --plain their IP addresses
+    Nodes {
+        #[structopt(long)]
+        ipv4: bool,
+
+        #[structopt(long)]
+        ipv6: bool,
+
+        /// Prints one IP address per line, e.g. for firewall allowlists
+        #[structopt(long)]
+        plain: bool,
+    },
+
+    /// Manages the recipients alerted when a check goes down or up
+    Recipients {
+        #[structopt(subcommand)]
+        action: RecipientAction,
+    },
+
+    /// Manages the status pages showing checks to the public
+    StatusPages {
+        #[structopt(subcommand)]
+        action: StatusPageAction,
+    },
+
+    /// Manages the webhooks called when a check goes down or up
+    Webhooks {
+        #[structopt(subcommand)]
+        action: WebhookAction,
     },
 }
 
-pub(crate) struct Parser<'a> {
-    pub(crate) matches: &'a ArgMatches<'a>,
-    pub(crate) parse_errors: Vec<String>,
-    pub(crate) successful_parse: bool,
+// The settings of a profile, for `config` and `config add-profile`. A doc comment here would
+// become the help of both subcommands.
+#[allow(dead_code)]
+#[derive(Debug, StructOpt)]
+struct Keys {
+    api_key: String,
+
+    read_only_api_key: String,
+
+    user_agent: String,
+
+    #[structopt(long)]
+    base_url: Option<String>,
+
+    #[structopt(long)]
+    requests_per_second: Option<f64>,
+
+    #[structopt(long)]
+    burst: Option<u32>,
 }
 
-impl Parser<'_> {
-    pub(crate) fn new<'a>(matches: &'a ArgMatches<'_>) -> Parser<'a> {
-        Parser {
-            matches,
-            parse_errors: vec![],
-            successful_parse: true,
-        }
+#[allow(dead_code)]
+#[derive(Debug, StructOpt)]
+enum ProfileAction {
+    /// Adds a profile, or replaces its settings
+    AddProfile {
+        name: String,
+
+        #[structopt(flatten)]
+        keys: Keys,
+    },
+    /// Lists the profiles, without their keys
+    List {},
+    /// Makes a profile the current one, used when --profile and UPDOWN_PROFILE aren't given
+    Use { name: String },
+    /// Removes a profile; the default profile can't be removed
+    Remove { name: String },
+}
+
+#[allow(dead_code)]
+#[derive(Debug, StructOpt)]
+enum BulkCommand {
+    /// Enables the selected checks
+    Enable {
+        #[structopt(flatten)]
+        options: BulkOptions,
+    },
+    /// Disables the selected checks
+    Disable {
+        #[structopt(flatten)]
+        options: BulkOptions,
+    },
+    /// Mutes the alerts of the selected checks
+    Mute {
+        /// forever, a date and time, or a time relative to now such as +2h
+        #[structopt(long, default_value = "forever", allow_hyphen_values = true)]
+        until: String,
+
+        #[structopt(flatten)]
+        options: BulkOptions,
+    },
+    /// Lifts the mute of the selected checks
+    Unmute {
+        #[structopt(flatten)]
+        options: BulkOptions,
+    },
+    /// Changes the period of the selected checks
+    SetPeriod {
+        /// The new period: 15, 30, 60, 120, 300, 600, 1800 or 3600
+        seconds: u32,
+
+        #[structopt(flatten)]
+        options: BulkOptions,
+    },
+    /// Deletes the selected checks
+    Delete {
+        #[structopt(flatten)]
+        options: BulkOptions,
+    },
+}
+
+// The selectors and settings shared by the bulk actions. A doc comment here would become the
+// help of every action.
+#[allow(dead_code)]
+#[derive(Debug, StructOpt)]
+struct BulkOptions {
+    /// Selects the checks whose alias matches a pattern, with * for any characters and ? for one
+    #[structopt(long)]
+    alias_glob: Option<String>,
+
+    /// Selects the checks whose URL matches a regular expression
+    #[structopt(long)]
+    url_regex: Option<String>,
+
+    /// Selects the checks that are down
+    #[structopt(long)]
+    down: bool,
+
+    /// Selects the disabled checks
+    #[structopt(long)]
+    disabled: bool,
+
+    /// Selects the checks run with this period, in seconds
+    #[structopt(long)]
+    period: Option<u32>,
+
+    /// Selects every check
+    #[structopt(long)]
+    all: bool,
+
+    /// How many requests are sent at a time
+    #[structopt(long, default_value = "4")]
+    concurrency: usize,
+
+    /// Goes ahead without asking for confirmation
+    #[structopt(short, long)]
+    yes: bool,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, StructOpt)]
+enum RecipientAction {
+    List {},
+
+    Add {
+        #[structopt(possible_values = &["email", "sms", "slack", "webhook"])]
+        r#type: String,
+
+        /// The email address, phone number or URL to notify
+        value: String,
+
+        #[structopt(long)]
+        name: Option<String>,
+
+        /// Alert this recipient from new checks by default
+        #[structopt(long)]
+        selected: bool,
+    },
+
+    Delete {
+        id: String,
+    },
+}
+
+#[allow(dead_code)]
+#[derive(Debug, StructOpt)]
+enum StatusPageAction {
+    List {},
+
+    Add {
+        #[structopt(long)]
+        name: Option<String>,
+
+        #[structopt(long)]
+        description: Option<String>,
+
+        #[structopt(long, possible_values = &["public", "protected", "private"])]
+        visibility: Option<String>,
+
+        #[structopt(long)]
+        access_key: Option<String>,
+
+        /// The token or alias of a check to show, repeated for each check
+        #[structopt(long, number_of_values = 1)]
+        check: Vec<String>,
+    },
+
+    Update {
+        token: String,
+
+        #[structopt(long)]
+        name: Option<String>,
+
+        #[structopt(long)]
+        description: Option<String>,
+
+        #[structopt(long, possible_values = &["public", "protected", "private"])]
+        visibility: Option<String>,
+
+        #[structopt(long)]
+        access_key: Option<String>,
+
+        /// The token or alias of a check to show, repeated for each check; replaces the checks
+        /// shown
+        #[structopt(long, number_of_values = 1)]
+        check: Vec<String>,
+    },
+
+    Delete {
+        token: String,
+    },
+}
+
+#[allow(dead_code)]
+#[derive(Debug, StructOpt)]
+enum WebhookAction {
+    List {},
+
+    Add { url: String },
+
+    Delete { id: String },
+}
+
+/// Stores the keys, user agent and rate limit given to the config subcommand in the selected
+/// profile.
+pub fn config(
+    subcommand_matches: &ArgMatches<'_>,
+    profile: Option<&str>,
+) -> Result<(), UpdownError> {
+    let config = parse_config(subcommand_matches)?;
+    let mut settings = Settings::load()?;
+    let name = settings.selected(profile);
+    settings.set_profile(&name, config);
+    settings.store()
+}
+
+/// Adds a profile, or replaces its settings.
+pub fn add_profile(subcommand_matches: &ArgMatches<'_>) -> Result<(), UpdownError> {
+    let config = parse_config(subcommand_matches)?;
+    let mut settings = Settings::load()?;
+    let name = subcommand_matches.value_of("name").unwrap_or_default();
+    settings.set_profile(name, config);
+    settings.store()
+}
+
+/// The profiles, with the one selected flagged as current.
+pub fn profiles(profile: Option<&str>) -> Result<Vec<Profile>, UpdownError> {
+    let settings = Settings::load()?;
+    let selected = settings.selected(profile);
+    Ok(settings
+        .names()
+        .into_iter()
+        .filter_map(|name| {
+            let config = settings.profile(name)?;
+            Some(Profile {
+                name: name.to_string(),
+                current: name == selected,
+                user_agent: config.user_agent.clone(),
+                base_url: config.base_url.clone(),
+            })
+        })
+        .collect())
+}
+
+pub fn use_profile(subcommand_matches: &ArgMatches<'_>) -> Result<(), UpdownError> {
+    let mut settings = Settings::load()?;
+    let name = subcommand_matches.value_of("name").unwrap_or_default();
+    known_profile(&settings, name)?;
+    settings.current_profile = Some(name)
+        .filter(|name| *name != DEFAULT_PROFILE)
+        .map(str::to_string);
+    settings.store()
+}
+
+pub fn remove_profile(subcommand_matches: &ArgMatches<'_>) -> Result<(), UpdownError> {
+    let mut settings = Settings::load()?;
+    let name = subcommand_matches.value_of("name").unwrap_or_default();
+    if name == DEFAULT_PROFILE {
+        return Err(UpdownError::BadArg(
+            "the default profile can't be removed".to_string(),
+        ));
+    }
+    known_profile(&settings, name)?;
+    settings.remove_profile(name);
+    settings.store()
+}
+
+fn known_profile(settings: &Settings, name: &str) -> Result<(), UpdownError> {
+    match settings.profile(name) {
+        Some(_) => Ok(()),
+        None => Err(UpdownError::BadArg(format!(
+            "unknown profile {}, the profiles are {}",
+            name,
+            settings.names().join(", ")
+        ))),
     }
+}
 
-    pub(crate) fn parse_value<T>(&mut self, key: &str) -> Option<T>
-    where
-        T: FromStr,
-        T::Err: Debug + Display,
+fn parse_config(subcommand_matches: &ArgMatches<'_>) -> Result<Config, UpdownError> {
+    let value = |key: &str| subcommand_matches.value_of(key).unwrap_or_default();
+    let mut parser = Parser::new(subcommand_matches);
+    let config = Config {
+        api_key: value("api-key").to_string(),
+        read_only_api_key: value("read-only-api-key").to_string(),
+        user_agent: value("user-agent").to_string(),
+        base_url: subcommand_matches.value_of("base-url").map(String::from),
+        requests_per_second: parser.parse_value("requests-per-second"),
+        burst: parser.parse_value("burst"),
+    };
+    if config
+        .requests_per_second
+        .is_some_and(|r| !r.is_finite() || r < MIN_REQUESTS_PER_SECOND)
     {
-        let result = self.matches.value_of(key);
-        match result {
-            Some(r) => {
-                let v = r.parse::<T>();
-                // Option::from(v.expect("Unsuccessful parse"))
-                match v {
-                    Ok(m) => Option::from(m),
-                    Err(e) => {
-                        self.parse_errors.push(format!(
-                            "{} ({} given)",
-                            e,
-                            self.matches.value_of("page").unwrap()
-                        ));
-                        self.successful_parse = false;
-                        None
-                    }
-                }
+        parser.reject("requests-per-second", RATE_REASON);
+    }
+    parser.finish()?;
+    if config.api_key.is_empty() && config.read_only_api_key.is_empty() {
+        return Err(UpdownError::BadArg("No api key provided".to_string()));
+    }
+    Ok(config)
+}
+
+pub async fn all(client: &Client) -> Result<Vec<Check>, UpdownError> {
+    client.all().await
+}
+
+pub async fn check(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Check, UpdownError> {
+    // `updown <token_or_url>` is a shorthand for `updown check <token_or_url>`
+    let reference = subcommand_matches
+        .value_of("token")
+        .or_else(|| subcommand_matches.value_of("token_or_url"))
+        .unwrap_or_default();
+    let metrics = subcommand_matches.is_present("metrics");
+    with_check(client, reference, |token| async move {
+        client.check(&token, metrics).await
+    })
+    .await
+}
+
+pub async fn metrics(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Metrics, UpdownError> {
+    let params = MetricsParams::parse(subcommand_matches)?;
+    with_check(client, params.token, |token| {
+        let params = params.clone();
+        async move {
+            let params = MetricsParams {
+                token: &token,
+                ..params
+            };
+            client.metrics(&params).await
+        }
+    })
+    .await
+}
+
+pub async fn downtimes(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Vec<Downtime>, UpdownError> {
+    let params = DowntimeParams::parse(subcommand_matches)?;
+    with_check(client, params.token, |token| {
+        let params = params.clone();
+        async move {
+            let params = DowntimeParams {
+                token: &token,
+                ..params
+            };
+            client.downtimes(&params).await
+        }
+    })
+    .await
+}
+
+pub async fn add(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Check, UpdownError> {
+    let params = CheckParams::parse_create(subcommand_matches)?;
+    check_locations(client, &params).await?;
+    client.create(&params).await
+}
+
+pub async fn update(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Check, UpdownError> {
+    let params = CheckParams::parse_update(subcommand_matches)?;
+    check_locations(client, &params).await?;
+    with_check(client, &params.token, |token| {
+        let params = CheckParams {
+            token,
+            ..params.clone()
+        };
+        async move { client.update(&params).await }
+    })
+    .await
+}
+
+/// Refuses disabled locations that aren't monitoring locations of updown.io.
+async fn check_locations(client: &Client, params: &CheckParams) -> Result<(), UpdownError> {
+    let locations = match &params.disabled_locations {
+        Some(locations) if !locations.is_empty() => locations,
+        _ => return Ok(()),
+    };
+    let nodes = client.nodes().await?;
+    let unknown: Vec<&str> = locations
+        .iter()
+        .filter(|l| !nodes.contains_key(*l))
+        .map(String::as_str)
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let known: Vec<&str> = nodes.keys().map(String::as_str).collect();
+    Err(UpdownError::BadArg(format!(
+        "--disabled-location: unknown location {}, the locations are {}",
+        unknown.join(", "),
+        known.join(", ")
+    )))
+}
+
+pub async fn delete(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Deleted, UpdownError> {
+    let reference = subcommand_matches.value_of("token").unwrap_or_default();
+    with_check(client, reference, |token| async move {
+        client.delete(&token).await
+    })
+    .await
+}
+
+/// Writes the manifest of the checks of the account to a file, or returns it to be printed.
+pub async fn export(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Option<String>, UpdownError> {
+    let file = subcommand_matches.value_of("file").map(Path::new);
+    let mut parser = Parser::new(subcommand_matches);
+    let format = parser
+        .parse_value("format")
+        .or_else(|| file.map(ManifestFormat::of))
+        .unwrap_or(ManifestFormat::Yaml);
+    parser.finish()?;
+    let manifest = Manifest::from_checks(&client.all().await?)?.to_string(format)?;
+    match file {
+        Some(file) => {
+            std::fs::write(file, manifest)?;
+            Ok(None)
+        }
+        None => Ok(Some(manifest)),
+    }
+}
+
+/// Applies a bulk action to the selected checks, once confirmed, reporting the progress on
+/// stderr. Returns `None` when the action isn't confirmed.
+pub async fn bulk(
+    client: &Client,
+    action: &str,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Option<Vec<Outcome>>, UpdownError> {
+    let action = BulkAction::parse(action, subcommand_matches)?;
+    let selector = Selector::parse(subcommand_matches)?;
+    let mut parser = Parser::new(subcommand_matches);
+    let concurrency = parser.parse_value("concurrency").unwrap_or(4);
+    parser.finish()?;
+
+    let checks: Vec<Check> = client
+        .all()
+        .await?
+        .into_iter()
+        .filter(|c| selector.matches(c))
+        .collect();
+    if checks.is_empty() {
+        eprintln!("No check matches");
+        return Ok(Some(vec![]));
+    }
+    if !subcommand_matches.is_present("yes") {
+        for check in &checks {
+            eprintln!(
+                "  {} {}",
+                check.token.as_deref().unwrap_or_default(),
+                manifest::name(check)
+            );
+        }
+        let these = match checks.len() {
+            1 => "this check".to_string(),
+            n => format!("these {} checks", n),
+        };
+        eprint!("{} {}? [y/N] ", action, these);
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") && !answer.trim().eq_ignore_ascii_case("yes") {
+            return Ok(None);
+        }
+    }
+
+    let total = checks.len();
+    let outcomes = bulk::run(client, action, &checks, concurrency, |done, outcome| {
+        let result = outcome.error.as_deref().unwrap_or("done");
+        eprintln!("[{}/{}] {}: {}", done, total, outcome.check, result);
+    })
+    .await;
+    let failed = outcomes.iter().filter(|o| o.error.is_some()).count();
+    eprintln!("{} done, {} failed", total - failed, failed);
+    Ok(Some(outcomes))
+}
+
+/// Copies a check to the account of another profile, unless that account already has a check
+/// with its alias or URL. Recipients belong to an account, so they aren't copied.
+pub async fn copy(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Check, UpdownError> {
+    let reference = subcommand_matches.value_of("token").unwrap_or_default();
+    let check = live_check(client, reference).await?;
+
+    let to = subcommand_matches.value_of("to").unwrap_or_default();
+    let destination = Client::from_config(&Config::load_profile(Some(to))?)?;
+    let existing = destination.all().await?;
+    let duplicate = existing.iter().find_map(|other| {
+        let url = check.url.as_deref();
+        if check.alias.is_some() && other.alias == check.alias {
+            check.alias.as_deref()
+        } else if url.is_some_and(|url| resolve::same_url(other.url.as_deref(), url)) {
+            url
+        } else {
+            None
+        }
+    });
+    if let Some(name) = duplicate {
+        return Err(UpdownError::BadArg(format!(
+            "the {} profile already has a check {}",
+            to, name
+        )));
+    }
+    destination.create(&CheckParams::from(&check)).await
+}
+
+/// The changes apply would make, without making them.
+pub async fn plan(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Vec<Change>, UpdownError> {
+    let path = subcommand_matches.value_of("manifest").unwrap_or_default();
+    let manifest = Manifest::load(Path::new(path))?;
+    let checks = client.all().await?;
+    manifest.plan(&checks, subcommand_matches.is_present("prune"))
+}
+
+/// The change `add`, `update` or `delete` would make with `--dry-run`, computed with the same
+/// checks as the request, which isn't sent.
+pub async fn dry_run(
+    client: &Client,
+    subcommand_name: &str,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Vec<Change>, UpdownError> {
+    let change = match subcommand_name {
+        "add" => {
+            let params = CheckParams::parse_create(subcommand_matches)?;
+            check_locations(client, &params).await?;
+            Change::from_params(params, None)
+        }
+        "update" => {
+            let mut params = CheckParams::parse_update(subcommand_matches)?;
+            check_locations(client, &params).await?;
+            let live = live_check(client, &params.token).await?;
+            params.token = live.token.clone().unwrap_or_default();
+            Change::from_params(params, Some(&live))
+        }
+        _ => {
+            let reference = subcommand_matches.value_of("token").unwrap_or_default();
+            Change::delete(&live_check(client, reference).await?)
+        }
+    };
+    Ok(vec![change])
+}
+
+/// Converges the checks of the account on a manifest, returning the changes made.
+pub async fn apply(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Vec<Change>, UpdownError> {
+    let path = subcommand_matches.value_of("manifest").unwrap_or_default();
+    let manifest = Manifest::load(Path::new(path))?;
+    let checks = client.all().await?;
+    let mut changes = manifest.plan(&checks, subcommand_matches.is_present("prune"))?;
+    // The locations of all the checks are checked before anything is changed
+    let locations = CheckParams {
+        disabled_locations: Some(
+            changes
+                .iter()
+                .flat_map(|c| c.params.disabled_locations.iter().flatten().cloned())
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    check_locations(client, &locations).await?;
+    for change in &mut changes {
+        match change.action {
+            Action::Create => change.token = client.create(&change.params).await?.token,
+            Action::Update => {
+                client.update(&change.params).await?;
+            }
+            Action::Delete => {
+                client
+                    .delete(change.token.as_deref().unwrap_or_default())
+                    .await?;
             }
-            _ => Option::None,
         }
     }
+    Ok(changes)
 }
 
-quick_error! {
+/// Whether `pulse` was given the pulse URL of a check, which is pinged without an API key,
+/// rather than its token.
+pub fn is_pulse_url(token: &str) -> bool {
+    token.starts_with("https://") || token.starts_with("http://")
+}
 
-    /// Error specific to updown
-    #[derive(Debug)]
-    pub enum  CliError{
-       BadArg (message : String ) { display("Failed to parse value(s) : {}", message )}
+/// Pings a pulse check. A token is looked up to find the pulse URL, which needs an API key; a
+/// pulse URL is used as it is.
+pub async fn pulse(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<(), UpdownError> {
+    let params = PulseParams::parse(subcommand_matches)?;
+    let token = subcommand_matches.value_of("token").unwrap_or_default();
+    if is_pulse_url(token) {
+        return client.pulse(token, &params).await;
     }
+    let check = client.check(token, false).await?;
+    match (check.check_type, check.pulse_url) {
+        (Some(CheckType::Pulse), Some(pulse_url)) => client.pulse(&pulse_url, &params).await,
+        _ => Err(UpdownError::BadArg(format!(
+            "{} is not a pulse check",
+            token
+        ))),
+    }
+}
+
+pub async fn nodes(client: &Client) -> Result<Nodes, UpdownError> {
+    client.nodes().await
 }
 
-pub(crate) async fn metrics(
-    config: Config,
+/// The IP addresses of the monitoring locations: IPv4 and/or IPv6 as asked, both if neither is.
+pub async fn node_ips(
+    client: &Client,
     subcommand_matches: &ArgMatches<'_>,
-) -> Result<Metrics, MessageError> {
-    let client = Client::new(
-        config.api_key.as_str(),
-        config.private_api_key.as_str(),
-        config.user_agent.as_str(),
-    );
-    let params = MetricsParams::parse(client.api_key, subcommand_matches);
-    client.metrics(&params).await
-}
-
-pub(crate) async fn downtimes(
-    config: Config,
+) -> Result<Vec<String>, UpdownError> {
+    let ipv4 = subcommand_matches.is_present("ipv4");
+    let ipv6 = subcommand_matches.is_present("ipv6");
+    let mut ips = vec![];
+    if ipv4 || !ipv6 {
+        ips.extend(client.nodes_ipv4().await?);
+    }
+    if ipv6 || !ipv4 {
+        ips.extend(client.nodes_ipv6().await?);
+    }
+    Ok(ips)
+}
+
+pub async fn webhooks(client: &Client) -> Result<Vec<Webhook>, UpdownError> {
+    client.webhooks().await
+}
+
+pub async fn add_webhook(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Webhook, UpdownError> {
+    let params = WebhookParams::parse(subcommand_matches)?;
+    client.create_webhook(&params).await
+}
+
+pub async fn delete_webhook(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Deleted, UpdownError> {
+    let id = subcommand_matches.value_of("id").unwrap_or_default();
+    client.delete_webhook(id).await
+}
+
+pub async fn recipients(client: &Client) -> Result<Vec<Recipient>, UpdownError> {
+    client.recipients().await
+}
+
+pub async fn add_recipient(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Recipient, UpdownError> {
+    let params = RecipientParams::parse(subcommand_matches)?;
+    client.create_recipient(&params).await
+}
+
+pub async fn delete_recipient(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<Deleted, UpdownError> {
+    let id = subcommand_matches.value_of("id").unwrap_or_default();
+    client.delete_recipient(id).await
+}
+
+pub async fn status_pages(client: &Client) -> Result<Vec<StatusPage>, UpdownError> {
+    client.status_pages().await
+}
+
+pub async fn add_status_page(
+    client: &Client,
+    subcommand_matches: &ArgMatches<'_>,
+) -> Result<StatusPage, UpdownError> {
+    let mut params = StatusPageParams::parse(subcommand_matches)?;
+    params.checks = resolve_checks(client, params.checks).await?;
+    client.create_status_page(&params).await
+}
+
+pub async fn update_status_page(
+    client: &Client,
     subcommand_matches: &ArgMatches<'_>,
-) -> Result<Downtimes, MessageError> {
-    let client = Client::new(
-        config.api_key.as_str(),
-        config.private_api_key.as_str(),
-        config.user_agent.as_str(),
-    );
-    let params = DowntimeParams::parse(client.api_key, &subcommand_matches);
-    client.downtimes(&params).await
-}
-
-pub(crate) async fn add(
-    config: Config,
+) -> Result<StatusPage, UpdownError> {
+    let mut params = StatusPageParams::parse(subcommand_matches)?;
+    params.checks = resolve_checks(client, params.checks).await?;
+    client.update_status_page(&params).await
+}
+
+pub async fn delete_status_page(
+    client: &Client,
     subcommand_matches: &ArgMatches<'_>,
-) -> Result<Check, MessageError> {
-    let client = Client::new(
-        config.api_key.as_str(),
-        config.private_api_key.as_str(),
-        config.user_agent.as_str(),
-    );
-    let params = CheckParams::parse_update(client.api_key, subcommand_matches);
-    match params {
-        Ok(p) => client.update(&p).await,
-        Err(e) => Err(MessageError::CommandFailed(e)),
+) -> Result<Deleted, UpdownError> {
+    let token = subcommand_matches.value_of("token").unwrap_or_default();
+    client.delete_status_page(token).await
+}
+
+/// Sends `request` for the check named by a token, an alias or a URL (see [`resolve::find`]).
+/// A reference that may be a token is tried as one first, so that a token costs no more than the
+/// request; the checks of the account are only loaded when the API doesn't know it. A token
+/// unknown to the API is reported by the API, any other reference matching no check as a bad
+/// argument.
+async fn with_check<T, F, R>(client: &Client, reference: &str, request: F) -> Result<T, UpdownError>
+where
+    F: Fn(String) -> R,
+    R: Future<Output = Result<T, UpdownError>>,
+{
+    let not_found = if resolve::may_be_token(reference) {
+        match request(reference.to_string()).await {
+            Err(UpdownError::ApiFailed(e)) if e.is_not_found() => Some(UpdownError::ApiFailed(e)),
+            result => return result,
+        }
+    } else {
+        None
+    };
+    let checks = client.all().await?;
+    match (resolve::find(&checks, reference)?, not_found) {
+        (Some(check), _) => request(check.token.clone().unwrap_or_default()).await,
+        (None, Some(error)) => Err(error),
+        (None, None) if resolve::is_pattern(reference) => Err(UpdownError::BadArg(format!(
+            "no check matches {}",
+            reference
+        ))),
+        (None, None) => Err(UpdownError::BadArg(format!(
+            "no check has the token, alias or URL {}",
+            reference
+        ))),
+    }
+}
+
+/// The check named by a token, an alias or a URL, as the API returns it.
+async fn live_check(client: &Client, reference: &str) -> Result<Check, UpdownError> {
+    with_check(client, reference, |token| async move {
+        client.check(&token, false).await
+    })
+    .await
+}
+
+/// Replaces the check aliases and URLs by the tokens of the checks they name.
+async fn resolve_checks(
+    client: &Client,
+    references: Option<Vec<String>>,
+) -> Result<Option<Vec<String>>, UpdownError> {
+    let references = match references {
+        Some(references) if !references.is_empty() => references,
+        other => return Ok(other),
+    };
+    let checks = client.all().await?;
+    let mut tokens = vec![];
+    for reference in references {
+        let check = resolve::find(&checks, &reference)?.ok_or_else(|| {
+            UpdownError::BadArg(format!(
+                "--check: no check has the token, alias or URL {}",
+                reference
+            ))
+        })?;
+        tokens.push(check.token.clone().unwrap_or_default());
     }
+    Ok(Some(tokens))
 }
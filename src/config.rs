@@ -1,18 +1,161 @@
-use confy::ConfyError;
+use crate::client::BASE_URL_ENV;
+use crate::rate_limit::RateLimiter;
+use crate::UpdownError;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+
+/// The name confy stores the settings under.
+const APP_NAME: &str = "updown-rust";
+
+/// Selects a profile, unless `--profile` is given.
+pub const PROFILE_ENV: &str = "UPDOWN_PROFILE";
+
+/// The profile stored at the top of the settings, as configured before profiles existed.
+pub const DEFAULT_PROFILE: &str = "default";
 
 /// Config represents a minimal configuration for the updown client.
-#[derive(Serialize, Deserialize, Default, Builder)]
-pub(crate) struct Config {
-    pub(crate) api_key: String,
+#[derive(Clone, Debug, Serialize, Deserialize, Default, Builder)]
+pub struct Config {
+    /// The full API key, needed to change checks.
+    pub api_key: String,
+
+    /// The read-only API key, used for GET requests when set. Stored as `private_api_key` by earlier
+    /// versions.
+    #[serde(default, alias = "private_api_key")]
+    pub read_only_api_key: String,
 
-    pub(crate) private_api_key: String,
+    pub user_agent: String,
 
-    pub(crate) user_agent: String,
+    /// Overrides the updown.io API location, e.g. for a proxy or a local stub server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub base_url: Option<String>,
+
+    /// Average number of requests sent per second. Requests are not rate limited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub requests_per_second: Option<f64>,
+
+    /// Number of requests that may be sent at once before `requests_per_second` applies. Defaults
+    /// to one second's worth of requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub burst: Option<u32>,
 }
 
 impl Config {
-    pub fn load_config() -> Result<Config, ConfyError> {
-        confy::load("updown-rust")
+    /// Loads the configuration of the selected profile (see [`Settings::selected`]).
+    /// `UPDOWN_BASE_URL`, if set, takes precedence over the stored base URL.
+    pub fn load_config() -> Result<Config, UpdownError> {
+        Config::load_profile(None)
+    }
+
+    /// Loads the configuration of a profile, the selected one when `profile` is `None`.
+    pub fn load_profile(profile: Option<&str>) -> Result<Config, UpdownError> {
+        let settings = Settings::load()?;
+        let name = settings.selected(profile);
+        let mut config = settings.profile(&name).cloned().ok_or_else(|| {
+            UpdownError::BadArg(format!(
+                "unknown profile {}, the profiles are {}",
+                name,
+                settings.names().join(", ")
+            ))
+        })?;
+        if let Ok(base_url) = env::var(BASE_URL_ENV) {
+            config.base_url = Some(base_url);
+        }
+        Ok(config)
+    }
+
+    /// The rate limiter described by `requests_per_second` and `burst`, if a rate is set, or an
+    /// error if the rate is not one [`RateLimiter::new`] accepts.
+    pub fn rate_limiter(&self) -> Result<Option<RateLimiter>, UpdownError> {
+        let requests_per_second = match self.requests_per_second {
+            Some(requests_per_second) => requests_per_second,
+            None => return Ok(None),
+        };
+        let burst = self
+            .burst
+            .unwrap_or_else(|| requests_per_second.ceil() as u32);
+        RateLimiter::new(requests_per_second, burst).map(Some)
+    }
+}
+
+/// Profile describes a stored profile, leaving out its keys.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Whether the profile is the one used
+    pub current: bool,
+    pub user_agent: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+}
+
+/// Settings is the stored configuration: the default profile at the top, as stored before
+/// profiles existed, and named profiles, e.g. for a staging and a production account.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub default: Config,
+
+    /// The profile used when none is given, set with `updown config use`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_profile: Option<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Config>,
+}
+
+impl Settings {
+    pub fn load() -> Result<Settings, UpdownError> {
+        Ok(confy::load(APP_NAME)?)
+    }
+
+    pub fn store(self) -> Result<(), UpdownError> {
+        Ok(confy::store(APP_NAME, self)?)
+    }
+
+    /// The profile to use: `profile` when given, else `UPDOWN_PROFILE`, else the current
+    /// profile, else the default one.
+    pub fn selected(&self, profile: Option<&str>) -> String {
+        profile
+            .map(str::to_string)
+            .or_else(|| env::var(PROFILE_ENV).ok().filter(|p| !p.is_empty()))
+            .or_else(|| self.current_profile.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Config> {
+        if name == DEFAULT_PROFILE {
+            Some(&self.default)
+        } else {
+            self.profiles.get(name)
+        }
+    }
+
+    /// Adds a profile, or replaces its configuration.
+    pub fn set_profile(&mut self, name: &str, config: Config) {
+        if name == DEFAULT_PROFILE {
+            self.default = config;
+        } else {
+            self.profiles.insert(name.to_string(), config);
+        }
+    }
+
+    /// Removes a named profile, returning whether it existed. The default profile stays.
+    pub fn remove_profile(&mut self, name: &str) -> bool {
+        if self.current_profile.as_deref() == Some(name) {
+            self.current_profile = None;
+        }
+        self.profiles.remove(name).is_some()
+    }
+
+    /// The default profile, then the named ones in order.
+    pub fn names(&self) -> Vec<&str> {
+        std::iter::once(DEFAULT_PROFILE)
+            .chain(self.profiles.keys().map(String::as_str))
+            .collect()
     }
 }
@@ -0,0 +1,195 @@
+//! A Rust client for [updown.io](https://updown.io).
+//!
+//! The crate provides:
+//!
+//!  + [`Client`], with methods for each of the updown API requests
+//!  + Structs for the messages used in the requests and responses (see [`messages`])
+//!  + [`Config`], a minimal configuration holding the user's keys
+//!
+//! The `updown` command-line tool is a thin binary built on top of this library. It needs the
+//! default `cli` feature, which also gives the messages `parse` functions reading its
+//! command-line options; libraries can leave it out with `default-features = false`.
+//!
+//! # Examples
+//!
+//! Create a client and ask for all checks:
+//!
+//! ```no_run
+//! use updown::Client;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), updown::UpdownError> {
+//! let client = Client::new("your-api-key", "your-read-only-api-key", "your-user-agent")?;
+//! let checks = client.all().await?;
+//! println!("{}", serde_json::to_string(&checks)?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Ask for the downtimes of a check:
+//!
+//! ```no_run
+//! use updown::messages::downtime::DowntimeParamsBuilder;
+//! use updown::Client;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), updown::UpdownError> {
+//! let client = Client::new("your-api-key", "your-read-only-api-key", "your-user-agent")?;
+//! let params = DowntimeParamsBuilder::default()
+//!     .token("your-token")
+//!     .page(2)
+//!     .build()
+//!     .unwrap();
+//! let downtimes = client.downtimes(&params).await?;
+//! println!("{}", serde_json::to_string(&downtimes)?);
+//! # Ok(())
+//! # }
+//! ```
+
+#[macro_use]
+extern crate quick_error;
+#[macro_use]
+extern crate derive_builder;
+
+pub mod bulk;
+pub mod client;
+pub mod config;
+pub mod manifest;
+pub mod messages;
+pub mod output;
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod parser;
+pub mod rate_limit;
+pub mod resolve;
+pub mod retry;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod time;
+
+pub use client::Client;
+pub use config::Config;
+
+use confy::ConfyError;
+use validator::ValidationErrors;
+
+quick_error! {
+
+    /// Error specific to updown. Every fallible operation in the crate returns this type, and the
+    /// CLI maps each kind of failure to its own exit code (see [`UpdownError::exit_code`]).
+    #[derive(Debug)]
+    pub enum UpdownError {
+        RequestFailed (cause : reqwest::Error){from() display("Request failed: {}", cause) source(cause)}
+        JsonFailed (cause : serde_json::Error){from() display("Invalid JSON: {}", cause) source(cause)}
+        UrlFailed (cause : url::ParseError){from() display("Invalid URL: {}", cause) source(cause)}
+        ApiFailed (cause : ApiError){from() display("The updown API returned an error: {}", cause) source(cause)}
+        Unauthorized (message : String){display("Not authorized: {}", message)}
+        BadArg (message : String){display("Failed to parse value(s): {}", message)}
+        ValidationFailed (cause : ValidationErrors){from() display("Invalid parameters: {}", describe(cause)) source(cause)}
+        ConfigurationFailed (cause : ConfyError){from() display("Configuration error: {}", cause) source(cause)}
+        YamlFailed (cause : serde_yaml::Error){from() display("Invalid YAML: {}", cause) source(cause)}
+        CsvFailed (cause : csv::Error){from() display("Failed to write CSV: {}", cause) source(cause)}
+        TomlFailed (cause : toml::ser::Error){from() display("Failed to write TOML: {}", cause) source(cause)}
+        IoFailed (cause : std::io::Error){from() display("I/O error: {}", cause) source(cause)}
+        ManifestInvalid (message : String){display("Invalid manifest: {}", message)}
+    }
+}
+
+impl UpdownError {
+    /// The process exit code the CLI uses for this error.
+    pub fn exit_code(&self) -> exitcode::ExitCode {
+        match self {
+            UpdownError::RequestFailed(e) if e.is_decode() => exitcode::PROTOCOL,
+            UpdownError::RequestFailed(_) => exitcode::UNAVAILABLE,
+            UpdownError::ApiFailed(e) if e.is_unauthorized() => exitcode::NOPERM,
+            UpdownError::ApiFailed(e) if e.is_rate_limited() || e.is_server_error() => {
+                exitcode::TEMPFAIL
+            }
+            UpdownError::JsonFailed(_) | UpdownError::ApiFailed(_) => exitcode::PROTOCOL,
+            UpdownError::Unauthorized(_) => exitcode::NOPERM,
+            UpdownError::BadArg(_) => exitcode::USAGE,
+            UpdownError::ValidationFailed(_)
+            | UpdownError::YamlFailed(_)
+            | UpdownError::ManifestInvalid(_) => exitcode::DATAERR,
+            UpdownError::IoFailed(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                exitcode::NOINPUT
+            }
+            UpdownError::IoFailed(_) => exitcode::IOERR,
+            UpdownError::CsvFailed(_) | UpdownError::TomlFailed(_) => exitcode::SOFTWARE,
+            UpdownError::UrlFailed(_) | UpdownError::ConfigurationFailed(_) => exitcode::CONFIG,
+        }
+    }
+}
+
+/// ApiError is an error response from the updown API: a non-success HTTP status, with the message
+/// from the `{"error": "..."}` body when there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ApiError {
+    /// 401 or 403: the API key is missing, invalid or lacks the rights for the request.
+    pub fn is_unauthorized(&self) -> bool {
+        self.status == 401 || self.status == 403
+    }
+
+    /// 404: usually an unknown token.
+    pub fn is_not_found(&self) -> bool {
+        self.status == 404
+    }
+
+    /// 429: too many requests.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status == 429
+    }
+
+    /// 5xx
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.status)
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = if self.is_unauthorized() {
+            "unauthorized"
+        } else if self.is_not_found() {
+            "not found"
+        } else if self.is_rate_limited() {
+            "rate limited"
+        } else if self.is_server_error() {
+            "server error"
+        } else {
+            "request rejected"
+        };
+        write!(f, "{} ({} {})", self.message, self.status, kind)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Lists each invalid field with the reason and the rejected value, e.g.
+/// `period: must be one of 15, 30, 60, 120, 300, 600, 1800 or 3600 (7 given)`
+pub(crate) fn describe(errors: &ValidationErrors) -> String {
+    let mut fields: Vec<_> = errors.field_errors().into_iter().collect();
+    fields.sort_by_key(|(field, _)| *field);
+    fields
+        .iter()
+        .flat_map(|(field, errors)| {
+            errors.iter().map(move |e| {
+                let reason = match &e.message {
+                    Some(message) => message.to_string(),
+                    None if e.code == "url" => "must be a valid URL".to_string(),
+                    None => e.code.to_string(),
+                };
+                match e.params.get("value") {
+                    Some(value) => format!("{}: {} ({} given)", field, reason, value),
+                    None => format!("{}: {}", field, reason),
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -1,177 +1,280 @@
-#[macro_use]
-extern crate quick_error;
-
-use client::Client;
+use clap::ArgMatches;
 use command::Updown;
-use config::Config;
-
-use crate::messages::check::CheckParams;
-use confy::ConfyError;
+use std::fmt;
+use std::io::IsTerminal;
 use std::process::exit;
 use structopt::StructOpt;
-use validator::ValidationErrors;
-
-extern crate clap;
-extern crate exitcode;
-extern crate validator;
-#[macro_use]
-extern crate derive_builder;
+use updown::config::Config;
+use updown::manifest::Change;
+use updown::output::{self, Format, Tabular};
+use updown::{Client, UpdownError};
 
-mod client;
 mod command;
-mod config;
-mod messages;
 
-/// This is a bit of a mish-mash and probably needs sorting out!
 #[tokio::main]
 async fn main() {
-    let matches = Updown::clap().get_matches();
-    let subcommand_name = matches.subcommand().0;
+    let matches = match Updown::clap().get_matches_safe() {
+        Ok(matches) => matches,
+        Err(e) if e.use_stderr() => {
+            eprintln!("{}", e.message);
+            exit(exitcode::USAGE);
+        }
+        // --help and --version
+        Err(e) => e.exit(),
+    };
 
-    if subcommand_name == "" {
-        Updown::clap().print_help();
+    if matches.subcommand_name().is_none() && !matches.is_present("token_or_url") {
+        Updown::clap().print_help().ok();
         exit(exitcode::NOINPUT);
     }
 
-    let subcommand_matches = matches.subcommand().1.unwrap();
-    if subcommand_name == "config" {
-        let mut api_key;
-        let mut private_api_key;
-        let mut user_agent;
+    if let Err(e) = run(&matches).await {
+        eprintln!("{}", e);
+        exit(e.exit_code());
+    }
+}
 
-        match subcommand_matches.value_of("api-key") {
-            Some(k) => {
-                api_key = k;
-            }
+/// Failure is how a command ends without succeeding: an error, or an outcome of the CLI which
+/// library callers never see.
+#[derive(Debug)]
+enum Failure {
+    Error(UpdownError),
+    /// `plan` found this many changes to make
+    Drifted(usize),
+    /// The user didn't confirm a bulk action
+    Cancelled,
+    /// Some requests of a bulk action failed, out of the total
+    BulkFailed(usize, usize),
+}
 
-            None => {
-                println!("No api key provided. Exiting.");
-                exit(exitcode::CONFIG);
-            }
+impl Failure {
+    fn exit_code(&self) -> exitcode::ExitCode {
+        match self {
+            Failure::Error(e) => e.exit_code(),
+            // As diff(1) does when files differ
+            Failure::Drifted(_) | Failure::Cancelled => 1,
+            // Running the action again retries the checks that failed
+            Failure::BulkFailed(..) => exitcode::TEMPFAIL,
         }
+    }
+}
 
-        match subcommand_matches.value_of("private-api-key") {
-            Some(k) => {
-                private_api_key = k;
-            }
+impl From<UpdownError> for Failure {
+    fn from(error: UpdownError) -> Failure {
+        Failure::Error(error)
+    }
+}
 
-            None => {
-                println!("No private (super) api key provided. Exiting.");
-                exit(exitcode::CONFIG);
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Error(e) => write!(f, "Error: {}", e),
+            Failure::Drifted(changes) => write!(
+                f,
+                "Error: {} change(s) needed to match the manifest",
+                changes
+            ),
+            Failure::Cancelled => f.write_str("Error: Cancelled, nothing was changed"),
+            Failure::BulkFailed(failed, total) => {
+                write!(f, "Error: {} of {} checks failed", failed, total)
             }
         }
+    }
+}
 
-        match subcommand_matches.value_of("user-agent") {
-            Some (a) => {
-                user_agent = a;
-            }
-            None => {
-                println!("User agent not provided. Using blank value");
-                user_agent = "";
-            }
-        }
+async fn run(matches: &ArgMatches<'_>) -> Result<(), Failure> {
+    // Without a subcommand, the check given by token_or_url is shown
+    let (subcommand_name, subcommand_matches) = match matches.subcommand() {
+        (name, Some(subcommand_matches)) => (name, subcommand_matches),
+        _ => ("check", matches),
+    };
 
-        let config = config::Config {
-            api_key: api_key.to_string(),
-            private_api_key: private_api_key.to_string(),
-            user_agent: user_agent.to_string(),
-        };
-        match confy::store("updown-rust", config) {
-            Ok(_c) => exit(exitcode::OK),
-            Err(e) => {
-                eprintln!("Failed to save config {}", e.to_string());
-                exit(exitcode::IOERR)
-            }
+    // Unset, changes are shown as a diff rather than in the default format
+    let output: Option<Format> = global_value(matches, "output").and_then(|f| f.parse().ok());
+    let format = output.unwrap_or_default();
+    let profile = global_value(matches, "profile");
+    if subcommand_name == "config" {
+        let result = match subcommand_matches.subcommand() {
+            ("add-profile", Some(matches)) => command::add_profile(matches),
+            ("list", _) => print(&command::profiles(profile)?, format),
+            ("use", Some(matches)) => command::use_profile(matches),
+            ("remove", Some(matches)) => command::remove_profile(matches),
+            _ => command::config(subcommand_matches, profile),
         };
+        return Ok(result?);
     }
 
-    let config;
-    match Config::load_config() {
-        Ok(c) => {
-            config = c;
-        }
-        Err(e) => {
-            eprintln!("Error {}", e);
-            exit(exitcode::CONFIG);
-        }
+    let config = Config::load_profile(profile)?;
+    // A pulse URL is pinged without an API key
+    let needs_key = !(subcommand_name == "pulse"
+        && subcommand_matches
+            .value_of("token")
+            .is_some_and(command::is_pulse_url));
+    if needs_key && config.api_key.is_empty() && config.read_only_api_key.is_empty() {
+        return Err(UpdownError::Unauthorized(
+            "no API key configured, run `updown config` first".to_string(),
+        )
+        .into());
     }
+    let client = Client::from_config(&config)?;
 
-    match subcommand_name {
-        "all" => {
-            let client = Client::new(
-                config.api_key.as_str(),
-                config.private_api_key.as_str(),
-                config.user_agent.as_str(),
-            );
-            let result = client.all().await;
-            let result = serde_json::to_string(&result.unwrap()).unwrap();
-            println!("{}", result);
-        }
-        "check" => {
-            let client = Client::new(
-                config.api_key.as_str(),
-                config.private_api_key.as_str(),
-                config.user_agent.as_str(),
-            );
-            let metrics = subcommand_matches.is_present("metrics");
-            let token = subcommand_matches.value_of("token").unwrap();
-            let result =
-                serde_json::to_string(&client.check(token, metrics).await.unwrap()).unwrap();
-            println!("{}", result);
+    let result = match subcommand_name {
+        "all" => print(&command::all(&client).await?, format),
+        "check" => print(&command::check(&client, subcommand_matches).await?, format),
+        "downtimes" => print(
+            &command::downtimes(&client, subcommand_matches).await?,
+            format,
+        ),
+        "metrics" => print(
+            &command::metrics(&client, subcommand_matches).await?,
+            format,
+        ),
+        "add" | "update" | "delete" if subcommand_matches.is_present("dry-run") => {
+            let changes = command::dry_run(&client, subcommand_name, subcommand_matches).await?;
+            print_changes(changes, subcommand_matches, output)
         }
-
-        "downtimes" => {
-            let result = command::downtimes(config, subcommand_matches).await;
-            println!("{}", serde_json::to_string(&result.unwrap()).unwrap());
-        }
-
-        "metrics" => {
-            let result = command::metrics(config, subcommand_matches).await;
-            println!("{}", serde_json::to_string(&result.unwrap()).unwrap());
+        "add" => print(&command::add(&client, subcommand_matches).await?, format),
+        "update" => print(&command::update(&client, subcommand_matches).await?, format),
+        "delete" => print(&command::delete(&client, subcommand_matches).await?, format),
+        "export" => {
+            if let Some(manifest) = command::export(&client, subcommand_matches).await? {
+                print!("{}", manifest);
+            }
+            Ok(())
         }
-
-        "add" => {
-            println!(
-                "{}",
-                serde_json::to_string(&command::add(config, subcommand_matches).await.unwrap())
-                    .unwrap()
-            );
+        "bulk" => match subcommand_matches.subcommand() {
+            (action, Some(matches)) => {
+                let outcomes = match command::bulk(&client, action, matches).await? {
+                    Some(outcomes) => outcomes,
+                    None => return Err(Failure::Cancelled),
+                };
+                print(&outcomes, format)?;
+                return match outcomes.iter().filter(|o| o.error.is_some()).count() {
+                    0 => Ok(()),
+                    failed => Err(Failure::BulkFailed(failed, outcomes.len())),
+                };
+            }
+            (action, None) => Err(UpdownError::BadArg(format!(
+                "unknown bulk action {}",
+                action
+            ))),
+        },
+        "copy" => print(&command::copy(&client, subcommand_matches).await?, format),
+        "plan" => {
+            let changes = command::plan(&client, subcommand_matches).await?;
+            let drifted = changes.len();
+            print_changes(changes, subcommand_matches, output)?;
+            return match drifted {
+                0 => Ok(()),
+                n => Err(Failure::Drifted(n)),
+            };
         }
-        "update" => {
-            let client = Client::new(
-                config.api_key.as_str(),
-                config.private_api_key.as_str(),
-                config.user_agent.as_str(),
-            );
-            let params = CheckParams::parse_update(&client.api_key, &subcommand_matches).unwrap();
-
-            let result = serde_json::to_string(&client.update(&params).await.unwrap()).unwrap();
-            println!("{}", result);
+        "apply" => print(&command::apply(&client, subcommand_matches).await?, format),
+        "pulse" => command::pulse(&client, subcommand_matches).await,
+        "nodes" if subcommand_matches.is_present("plain") => {
+            for ip in command::node_ips(&client, subcommand_matches).await? {
+                println!("{}", ip);
+            }
+            Ok(())
         }
-        "delete" => {
-            let client = Client::new(
-                config.api_key.as_str(),
-                config.private_api_key.as_str(),
-                config.user_agent.as_str(),
-            );
-            let token = subcommand_matches.value_of("token").unwrap();
-            let result = serde_json::to_string(&client.delete(token).await.unwrap()).unwrap();
-            println!("{}", result);
+        "nodes"
+            if subcommand_matches.is_present("ipv4") || subcommand_matches.is_present("ipv6") =>
+        {
+            print(
+                &command::node_ips(&client, subcommand_matches).await?,
+                format,
+            )
         }
+        "nodes" => print(&command::nodes(&client).await?, format),
+        "recipients" => match subcommand_matches.subcommand() {
+            ("list", _) => print(&command::recipients(&client).await?, format),
+            ("add", Some(matches)) => {
+                print(&command::add_recipient(&client, matches).await?, format)
+            }
+            ("delete", Some(matches)) => {
+                print(&command::delete_recipient(&client, matches).await?, format)
+            }
+            (action, _) => Err(UpdownError::BadArg(format!(
+                "unknown recipients action {}",
+                action
+            ))),
+        },
+        "status-pages" => match subcommand_matches.subcommand() {
+            ("list", _) => print(&command::status_pages(&client).await?, format),
+            ("add", Some(matches)) => {
+                print(&command::add_status_page(&client, matches).await?, format)
+            }
+            ("update", Some(matches)) => print(
+                &command::update_status_page(&client, matches).await?,
+                format,
+            ),
+            ("delete", Some(matches)) => print(
+                &command::delete_status_page(&client, matches).await?,
+                format,
+            ),
+            (action, _) => Err(UpdownError::BadArg(format!(
+                "unknown status-pages action {}",
+                action
+            ))),
+        },
+        "webhooks" => match subcommand_matches.subcommand() {
+            ("list", _) => print(&command::webhooks(&client).await?, format),
+            ("add", Some(matches)) => print(&command::add_webhook(&client, matches).await?, format),
+            ("delete", Some(matches)) => {
+                print(&command::delete_webhook(&client, matches).await?, format)
+            }
+            (action, _) => Err(UpdownError::BadArg(format!(
+                "unknown webhooks action {}",
+                action
+            ))),
+        },
+        _ => Err(UpdownError::BadArg(format!(
+            "unknown subcommand {}",
+            subcommand_name
+        ))),
+    };
+    Ok(result?)
+}
 
-        _ => unimplemented!(),
+/// The value of a global flag, which may be given after any of the subcommands.
+fn global_value<'a>(matches: &'a ArgMatches<'_>, key: &str) -> Option<&'a str> {
+    let mut value = None;
+    let mut matches = Some(matches);
+    while let Some(m) = matches {
+        if m.occurrences_of(key) > 0 {
+            value = m.value_of(key);
+        }
+        matches = m.subcommand().1;
     }
+    value
 }
 
-quick_error! {
+fn print<T: Tabular>(value: &T, format: Format) -> Result<(), UpdownError> {
+    println!("{}", output::render(value, format, color())?);
+    Ok(())
+}
 
-    /// Error specific to updown
-    #[derive(Debug)]
-    pub enum UpdownError {
-        MessageFailed (cause : messages::MessageError){from()}
-        ValidationFailed (cause : ValidationErrors){}
-        ConfigurationFailed (cause : ConfyError){from()}
+/// Prints planned changes as a diff, in the `--output` format when one is given, or as a JSON
+/// patch with `--json-patch`, pretty-printed unless another format is given.
+fn print_changes(
+    changes: Vec<Change>,
+    matches: &ArgMatches<'_>,
+    output: Option<Format>,
+) -> Result<(), UpdownError> {
+    if matches.is_present("json-patch") {
+        let patch = output::json_patch(&changes);
+        print(&patch, output.unwrap_or(Format::JsonPretty))
+    } else if let Some(format) = output {
+        print(&changes, format)
+    } else {
+        if !changes.is_empty() {
+            println!("{}", output::unified_diff(&changes, color()));
+        }
+        Ok(())
     }
 }
 
-const CHECKS_URL: &'static str = "https://updown.io/api/checks";
+/// Colours are written to terminals only, unless NO_COLOR is set.
+fn color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
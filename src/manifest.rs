@@ -0,0 +1,517 @@
+//! Manifests describe the checks an account should have, so they can be kept in version control
+//! and applied with `updown apply`.
+//!
+//! A manifest is a YAML, TOML or JSON file listing checks by URL, or by alias for pulse checks:
+//!
+//! ```yaml
+//! checks:
+//!   - url: https://shop.example.com
+//!     alias: shop
+//!     period: 60
+//!     string_match: Add to cart
+//!     disabled_locations: [syd]
+//!     headers:
+//!       Authorization: Bearer 1234
+//!   - type: pulse
+//!     alias: nightly backup
+//!     period: 3600
+//! ```
+//!
+//! Only the fields given are managed: a field left out keeps whatever value the check has.
+
+use crate::messages::check::{known_period, Check, CheckParams, CheckType, Period};
+use crate::resolve::same_url;
+use crate::UpdownError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use validator::Validate;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub checks: Vec<CheckSpec>,
+}
+
+/// CheckSpec is the desired state of a check. It is matched with a check of the account by alias,
+/// or failing that by URL.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct CheckSpec {
+    /// HTTP unless given
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub check_type: Option<CheckType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(url)]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(custom = "known_period")]
+    pub period: Option<Period>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apdex_t: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub string_match: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_locations: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<bool>,
+    // Last, as TOML writes tables after the plain values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<BTreeMap<String, String>>,
+}
+
+impl CheckSpec {
+    /// The alias, or the URL of a check without one.
+    pub fn key(&self) -> &str {
+        self.alias
+            .as_deref()
+            .or(self.url.as_deref())
+            .unwrap_or_default()
+    }
+
+    fn is_pulse(&self) -> bool {
+        self.check_type == Some(CheckType::Pulse)
+    }
+}
+
+impl From<&Check> for CheckSpec {
+    fn from(check: &Check) -> CheckSpec {
+        let pulse = check.check_type == Some(CheckType::Pulse);
+        let mut locations = check.disabled_locations.clone().unwrap_or_default();
+        locations.sort();
+        let headers: BTreeMap<String, String> = check
+            .custom_headers
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        CheckSpec {
+            check_type: if pulse {
+                check.check_type.clone()
+            } else {
+                None
+            },
+            url: if pulse { None } else { check.url.clone() },
+            alias: check.alias.clone(),
+            period: check.period,
+            apdex_t: check.apdex_t,
+            string_match: check.string_match.clone().filter(|s| !s.is_empty()),
+            disabled_locations: Some(locations).filter(|l| !l.is_empty()),
+            enabled: check.enabled,
+            published: check.published,
+            headers: Some(headers).filter(|h| !h.is_empty()),
+        }
+    }
+}
+
+/// Action is what `updown apply` does to a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Change is a step towards the state described by a manifest.
+#[derive(Clone, Debug, Serialize)]
+pub struct Change {
+    pub action: Action,
+    /// The alias or URL of the check
+    pub check: String,
+    /// Missing for a check yet to be created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// The fields set by a create or an update
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diff: Vec<Diff>,
+    /// The parameters of the create or update request
+    #[serde(skip)]
+    pub params: CheckParams,
+}
+
+/// Diff is a field of a check that differs from the manifest: `old` is null for a field the check
+/// doesn't have yet.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diff {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl Change {
+    /// The change a create request, or an update request of the `live` check, would make with
+    /// `params`. Unlike a manifest, the parameters are compared as the API takes them.
+    pub fn from_params(params: CheckParams, live: Option<&Check>) -> Change {
+        let fields = match serde_json::to_value(&params) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Default::default(),
+        };
+        let old = live.and_then(|c| serde_json::to_value(c).ok());
+        let diff = fields
+            .into_iter()
+            .filter(|(field, _)| field != "token")
+            .map(|(field, new)| {
+                let old = old
+                    .as_ref()
+                    .and_then(|old| old.get(&field))
+                    .cloned()
+                    .unwrap_or_default();
+                Diff { field, old, new }
+            })
+            .filter(|diff| diff.old != diff.new)
+            .collect();
+        Change {
+            action: if live.is_some() {
+                Action::Update
+            } else {
+                Action::Create
+            },
+            check: match live {
+                Some(check) => name(check),
+                None => params
+                    .alias
+                    .clone()
+                    .or_else(|| params.url.clone())
+                    .unwrap_or_default(),
+            },
+            token: live.and_then(|c| c.token.clone()),
+            diff,
+            params,
+        }
+    }
+
+    /// The deletion of a check.
+    pub fn delete(check: &Check) -> Change {
+        Change {
+            action: Action::Delete,
+            check: name(check),
+            token: check.token.clone(),
+            diff: vec![],
+            params: CheckParams::default(),
+        }
+    }
+}
+
+/// The file formats of a manifest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ManifestFormat {
+    pub const NAMES: [&'static str; 3] = ["yaml", "toml", "json"];
+
+    /// TOML or JSON when the file name ends with `.toml` or `.json`, YAML otherwise.
+    pub fn of(path: &Path) -> ManifestFormat {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => ManifestFormat::Toml,
+            Some("json") => ManifestFormat::Json,
+            _ => ManifestFormat::Yaml,
+        }
+    }
+}
+
+impl FromStr for ManifestFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yaml" => Ok(ManifestFormat::Yaml),
+            "toml" => Ok(ManifestFormat::Toml),
+            "json" => Ok(ManifestFormat::Json),
+            _ => Err(format!(
+                "must be one of {}",
+                ManifestFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl Manifest {
+    /// Reads a manifest, in the format given by its file name (see [`ManifestFormat::of`]).
+    pub fn load(path: &Path) -> Result<Manifest, UpdownError> {
+        let text = std::fs::read_to_string(path)?;
+        let invalid = |e: &dyn std::fmt::Display| {
+            UpdownError::ManifestInvalid(format!("{}: {}", path.display(), e))
+        };
+        let manifest: Manifest = match ManifestFormat::of(path) {
+            ManifestFormat::Toml => toml::from_str(&text).map_err(|e| invalid(&e))?,
+            ManifestFormat::Json => serde_json::from_str(&text).map_err(|e| invalid(&e))?,
+            ManifestFormat::Yaml => serde_yaml::from_str(&text).map_err(|e| invalid(&e))?,
+        };
+        manifest.validate().map_err(|e| invalid(&e))?;
+        Ok(manifest)
+    }
+
+    /// The manifest of the given checks, which `apply` leaves as they are. The checks are sorted
+    /// by alias or URL, and empty string matches, locations and headers are left out. Fails,
+    /// listing them, when some checks couldn't be matched back: pulse checks without an alias,
+    /// and checks sharing an alias or URL.
+    pub fn from_checks(checks: &[Check]) -> Result<Manifest, UpdownError> {
+        let mut problems = vec![];
+        let mut owners: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for check in checks {
+            let token = check.token.as_deref().unwrap_or_default();
+            if check.check_type == Some(CheckType::Pulse) && check.alias.is_none() {
+                problems.push(format!("{} is a pulse check without an alias", token));
+            }
+            for name in check.alias.iter().chain(&check.url) {
+                let tokens = owners.entry(name.trim_end_matches('/')).or_default();
+                if tokens.last() != Some(&token) {
+                    tokens.push(token);
+                }
+            }
+        }
+        for (name, tokens) in owners.into_iter().filter(|(_, tokens)| tokens.len() > 1) {
+            problems.push(format!("{} all have {}", tokens.join(", "), name));
+        }
+        if !problems.is_empty() {
+            return Err(UpdownError::ManifestInvalid(format!(
+                "some checks can't be told apart, give them unique aliases: {}",
+                problems.join("; ")
+            )));
+        }
+
+        let mut specs: Vec<CheckSpec> = checks.iter().map(CheckSpec::from).collect();
+        specs.sort_by(|a, b| a.key().cmp(b.key()));
+        let manifest = Manifest { checks: specs };
+        manifest.validate().map_err(UpdownError::ManifestInvalid)?;
+        Ok(manifest)
+    }
+
+    pub fn to_string(&self, format: ManifestFormat) -> Result<String, UpdownError> {
+        Ok(match format {
+            ManifestFormat::Yaml => serde_yaml::to_string(self)?,
+            ManifestFormat::Toml => toml::to_string(self)?,
+            ManifestFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+        })
+    }
+
+    /// Refuses checks that can't be created or matched: an HTTP check needs a URL, a pulse check
+    /// an alias and no URL, and no two checks may have the same alias or URL.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut keys = HashSet::new();
+        for spec in &self.checks {
+            match (spec.is_pulse(), &spec.url, &spec.alias) {
+                (false, None, _) => return Err("a check has no url".to_string()),
+                (true, Some(url), _) => {
+                    return Err(format!("the pulse check {} has a url", url));
+                }
+                (true, _, None) => return Err("a pulse check has no alias".to_string()),
+                _ => {}
+            }
+            if let Err(e) = spec.validate() {
+                return Err(format!("{}: {}", spec.key(), crate::describe(&e)));
+            }
+            let names = spec.alias.iter().chain(&spec.url);
+            for name in names.map(|name| name.trim_end_matches('/')) {
+                if !keys.insert(name) {
+                    return Err(format!("{} is listed twice", name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The changes turning `checks` into the checks of the manifest, in order: creates and
+    /// updates as listed in the manifest, then with `prune` the deletion of every check the
+    /// manifest doesn't list.
+    pub fn plan(&self, checks: &[Check], prune: bool) -> Result<Vec<Change>, UpdownError> {
+        let mut matched: Vec<Option<usize>> = vec![None; self.checks.len()];
+        let mut taken = vec![false; checks.len()];
+        // Aliases first, so a check whose URL changes is still recognised
+        for (spec, matched) in self.checks.iter().zip(matched.iter_mut()) {
+            if let Some(alias) = &spec.alias {
+                *matched = find(checks, &taken, |c| c.alias.as_ref() == Some(alias))?;
+                if let Some(index) = *matched {
+                    taken[index] = true;
+                }
+            }
+        }
+        for (spec, matched) in self.checks.iter().zip(matched.iter_mut()) {
+            if let (None, Some(url)) = (*matched, &spec.url) {
+                *matched = find(checks, &taken, |c| same_url(c.url.as_deref(), url))?;
+                if let Some(index) = *matched {
+                    taken[index] = true;
+                }
+            }
+        }
+
+        let mut changes = vec![];
+        for (spec, matched) in self.checks.iter().zip(matched) {
+            let live = matched.map(|index| &checks[index]);
+            let (diff, params) = compare(spec, live);
+            let action = match live {
+                None => Action::Create,
+                Some(_) if !diff.is_empty() => Action::Update,
+                Some(_) => continue,
+            };
+            changes.push(Change {
+                action,
+                check: spec.key().to_string(),
+                token: live.and_then(|c| c.token.clone()),
+                diff,
+                params,
+            });
+        }
+        if prune {
+            for (check, _) in checks.iter().zip(taken).filter(|(_, taken)| !taken) {
+                changes.push(Change::delete(check));
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// The index of the only check not yet taken that matches, if any.
+fn find(
+    checks: &[Check],
+    taken: &[bool],
+    matches: impl Fn(&Check) -> bool,
+) -> Result<Option<usize>, UpdownError> {
+    let found: Vec<usize> = (0..checks.len())
+        .filter(|&i| !taken[i] && matches(&checks[i]))
+        .collect();
+    match found.as_slice() {
+        [] => Ok(None),
+        [index] => Ok(Some(*index)),
+        _ => Err(UpdownError::BadArg(format!(
+            "several checks match {}, give them different aliases: {}",
+            name(&checks[found[0]]),
+            found
+                .iter()
+                .filter_map(|&i| checks[i].token.as_deref())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// Lists the fields of `spec` that differ from `live`, every field given for a check to create,
+/// with the request parameters setting them.
+fn compare(spec: &CheckSpec, live: Option<&Check>) -> (Vec<Diff>, CheckParams) {
+    let mut diff = vec![];
+    let mut params = CheckParams {
+        token: live.and_then(|c| c.token.clone()).unwrap_or_default(),
+        check_type: if live.is_none() {
+            spec.check_type.clone()
+        } else {
+            None
+        },
+        ..Default::default()
+    };
+    // updown may add a trailing `/` to the URLs it is given
+    let url = live.and_then(|c| match (&spec.url, &c.url) {
+        (Some(url), Some(live_url)) if same_url(Some(live_url), url) => Some(url.clone()),
+        _ => c.url.clone(),
+    });
+    if changed(&mut diff, "url", &spec.url, url) {
+        params.url = spec.url.clone();
+    }
+    if changed(
+        &mut diff,
+        "alias",
+        &spec.alias,
+        live.and_then(|c| c.alias.clone()),
+    ) {
+        params.alias = spec.alias.clone();
+    }
+    if changed(
+        &mut diff,
+        "period",
+        &spec.period,
+        live.and_then(|c| c.period),
+    ) {
+        params.period = spec.period;
+    }
+    if changed(
+        &mut diff,
+        "apdex_t",
+        &spec.apdex_t,
+        live.and_then(|c| c.apdex_t),
+    ) {
+        params.apdex_t = spec.apdex_t;
+    }
+    let string_match = live.map(|c| c.string_match.clone().unwrap_or_default());
+    if changed(&mut diff, "string_match", &spec.string_match, string_match) {
+        params.string_match = spec.string_match.clone();
+    }
+    // The order of the locations doesn't matter
+    let sorted = |locations: Option<Vec<String>>| {
+        let mut locations = locations.unwrap_or_default();
+        locations.sort();
+        locations
+    };
+    let desired = spec.disabled_locations.clone().map(|l| sorted(Some(l)));
+    let locations = live.map(|c| sorted(c.disabled_locations.clone()));
+    if changed(&mut diff, "disabled_locations", &desired, locations) {
+        params.disabled_locations = desired;
+    }
+    if changed(
+        &mut diff,
+        "enabled",
+        &spec.enabled,
+        live.and_then(|c| c.enabled),
+    ) {
+        params.enabled = spec.enabled;
+    }
+    if changed(
+        &mut diff,
+        "published",
+        &spec.published,
+        live.and_then(|c| c.published),
+    ) {
+        params.published = spec.published;
+    }
+    let headers = live.map(|c| {
+        c.custom_headers
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+    });
+    if changed(&mut diff, "headers", &spec.headers, headers) {
+        params.custom_headers = spec.headers.clone().map(|h| h.into_iter().collect());
+    }
+    (diff, params)
+}
+
+/// Records `field` when the manifest gives it a value other than `actual`.
+fn changed<T: Serialize + PartialEq>(
+    diff: &mut Vec<Diff>,
+    field: &str,
+    desired: &Option<T>,
+    actual: Option<T>,
+) -> bool {
+    match desired {
+        Some(desired) if actual.as_ref() != Some(desired) => {
+            diff.push(Diff {
+                field: field.to_string(),
+                old: serde_json::to_value(actual).unwrap_or_default(),
+                new: serde_json::to_value(desired).unwrap_or_default(),
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The alias, URL or token of a check of the account.
+pub fn name(check: &Check) -> String {
+    check
+        .alias
+        .clone()
+        .or_else(|| check.url.clone())
+        .or_else(|| check.token.clone())
+        .unwrap_or_default()
+}
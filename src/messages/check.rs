@@ -1,164 +1,482 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 
-use crate::command::{CliError, Parser};
 use crate::messages::metric::Message;
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+use crate::time::Time;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+#[cfg(feature = "cli")]
 use clap::ArgMatches;
-use validator::{Validate, ValidationError};
+#[cfg(feature = "cli")]
+use reqwest::header::{HeaderName, HeaderValue};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use validator::{Validate, ValidationError, ValidationErrors};
 
-/// Checks represents the output of /api/checks/:token/Checks
-/// Possible return values are an array of Check messages or an error message.
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-pub enum Checks {
-    Error { error: Option<String> },
-    Checks(Vec<Check>),
+/// CheckType tells checks polled by updown from pulse checks, which are pinged by cron jobs
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum CheckType {
+    Http,
+    Pulse,
+    /// A check type this crate doesn't know about yet, as the API named it
+    Other(String),
+}
+
+impl fmt::Display for CheckType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckType::Http => "http",
+            CheckType::Pulse => "pulse",
+            CheckType::Other(name) => name,
+        })
+    }
+}
+
+/// Any name is accepted, so that a new check type doesn't make the checks unreadable.
+impl From<String> for CheckType {
+    fn from(name: String) -> CheckType {
+        match name.as_str() {
+            "http" => CheckType::Http,
+            "pulse" => CheckType::Pulse,
+            _ => CheckType::Other(name),
+        }
+    }
+}
+
+impl From<CheckType> for String {
+    fn from(check_type: CheckType) -> String {
+        check_type.to_string()
+    }
+}
+
+impl FromStr for CheckType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http" => Ok(CheckType::Http),
+            "pulse" => Ok(CheckType::Pulse),
+            _ => Err("must be http or pulse".to_string()),
+        }
+    }
+}
+
+/// Period is the interval between two runs of a check. It is sent to the API as a number of
+/// seconds.
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(into = "u32")]
+pub enum Period {
+    Secs15,
+    Secs30,
+    Min1,
+    Min2,
+    Min5,
+    Min10,
+    Min30,
+    Hour1,
+    /// A period this crate doesn't know about yet, in seconds. It is kept as the API returns it,
+    /// but refused on input: [`Period::try_from`] only gives the periods above.
+    Other(u32),
+}
+
+impl Period {
+    pub const ALL: [Period; 8] = [
+        Period::Secs15,
+        Period::Secs30,
+        Period::Min1,
+        Period::Min2,
+        Period::Min5,
+        Period::Min10,
+        Period::Min30,
+        Period::Hour1,
+    ];
+
+    pub fn as_secs(self) -> u32 {
+        match self {
+            Period::Secs15 => 15,
+            Period::Secs30 => 30,
+            Period::Min1 => 60,
+            Period::Min2 => 120,
+            Period::Min5 => 300,
+            Period::Min10 => 600,
+            Period::Min30 => 1800,
+            Period::Hour1 => 3600,
+            Period::Other(secs) => secs,
+        }
+    }
+
+    pub fn as_duration(self) -> Duration {
+        Duration::from_secs(u64::from(self.as_secs()))
+    }
+
+    /// Reads a period given by users, reporting one that isn't accepted as an invalid `period`
+    /// field rather than a malformed argument.
+    pub fn validated(secs: u32) -> Result<Period, ValidationErrors> {
+        Period::try_from(secs).map_err(|error| {
+            let mut errors = ValidationErrors::new();
+            errors.add("period", error);
+            errors
+        })
+    }
+}
+
+/// Any number of seconds is accepted, so that a new period doesn't make the checks unreadable.
+impl<'de> Deserialize<'de> for Period {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let secs = u32::deserialize(deserializer)?;
+        Ok(Period::try_from(secs).unwrap_or(Period::Other(secs)))
+    }
+}
+
+/// Refuses [`Period::Other`], for the periods given by users, e.g. in a manifest.
+pub fn known_period(period: &Period) -> Result<(), ValidationError> {
+    match period {
+        Period::Other(secs) => Period::try_from(*secs).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+impl From<Period> for u32 {
+    fn from(period: Period) -> u32 {
+        period.as_secs()
+    }
+}
+
+impl TryFrom<u32> for Period {
+    type Error = ValidationError;
+
+    fn try_from(secs: u32) -> Result<Self, Self::Error> {
+        Period::ALL
+            .iter()
+            .copied()
+            .find(|p| p.as_secs() == secs)
+            .ok_or_else(|| {
+                let mut error = ValidationError::new("period");
+                error.message =
+                    Some("must be one of 15, 30, 60, 120, 300, 600, 1800 or 3600".into());
+                error.add_param("value".into(), &secs);
+                error
+            })
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_secs())
+    }
+}
+
+/// HttpVerb is the request method used by an HTTP check. `GetHead` is updown's default: a HEAD
+/// request, or a GET when the body is needed for `string_match`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum HttpVerb {
+    GetHead,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Options,
+    /// A method this crate doesn't know about yet, as the API named it
+    Other(String),
+}
+
+impl fmt::Display for HttpVerb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HttpVerb::GetHead => "GET/HEAD",
+            HttpVerb::Post => "POST",
+            HttpVerb::Put => "PUT",
+            HttpVerb::Patch => "PATCH",
+            HttpVerb::Delete => "DELETE",
+            HttpVerb::Options => "OPTIONS",
+            HttpVerb::Other(name) => name,
+        })
+    }
+}
+
+/// Any method is accepted, so that a new one doesn't make the checks unreadable; the methods
+/// given by users are parsed with [`HttpVerb::from_str`].
+impl From<String> for HttpVerb {
+    fn from(name: String) -> HttpVerb {
+        match name.as_str() {
+            "GET/HEAD" => HttpVerb::GetHead,
+            "POST" => HttpVerb::Post,
+            "PUT" => HttpVerb::Put,
+            "PATCH" => HttpVerb::Patch,
+            "DELETE" => HttpVerb::Delete,
+            "OPTIONS" => HttpVerb::Options,
+            _ => HttpVerb::Other(name),
+        }
+    }
+}
+
+impl From<HttpVerb> for String {
+    fn from(verb: HttpVerb) -> String {
+        verb.to_string()
+    }
+}
+
+impl FromStr for HttpVerb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "GET/HEAD" | "GET" | "HEAD" => Ok(HttpVerb::GetHead),
+            "POST" => Ok(HttpVerb::Post),
+            "PUT" => Ok(HttpVerb::Put),
+            "PATCH" => Ok(HttpVerb::Patch),
+            "DELETE" => Ok(HttpVerb::Delete),
+            "OPTIONS" => Ok(HttpVerb::Options),
+            _ => Err("must be one of GET/HEAD, POST, PUT, PATCH, DELETE or OPTIONS".to_string()),
+        }
+    }
+}
+
+/// MuteUntil silences the alerts of a check until a given time, or until it is unmuted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MuteUntil {
+    Time(DateTime<Utc>),
+    Forever,
+    /// Lifts a mute; sent as an empty value
+    Unmuted,
+}
+
+impl Serialize for MuteUntil {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MuteUntil::Time(time) => time.serialize(serializer),
+            MuteUntil::Forever => serializer.serialize_str("forever"),
+            MuteUntil::Unmuted => serializer.serialize_str(""),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MuteUntil {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        match value.as_str() {
+            "forever" => return Ok(MuteUntil::Forever),
+            "" => return Ok(MuteUntil::Unmuted),
+            _ => {}
+        }
+        DateTime::parse_from_rfc3339(&value)
+            .map(|time| MuteUntil::Time(time.with_timezone(&Utc)))
+            .map_err(de::Error::custom)
+    }
+}
+
+impl FromStr for MuteUntil {
+    type Err = String;
+
+    /// `forever`, or a [`Time`] such as `+2h` or `2020-10-20 14:00 Europe/Paris`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("forever") {
+            return Ok(MuteUntil::Forever);
+        }
+        s.parse::<Time>()
+            .map(|Time(time)| MuteUntil::Time(time))
+            .map_err(|e| format!("must be forever or a time: {}", e))
+    }
 }
 
 /// Check represents the output of /api/checks/:token; Vec<Check> is deserialized from /api/checks
 #[derive(Clone, Validate, Serialize, Deserialize, Debug, Default)]
 pub struct Check {
-    pub(crate) token: Option<String>,
+    pub token: Option<String>,
+    /// Missing from checks created before pulse checks existed, which are all HTTP checks
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub check_type: Option<CheckType>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[validate(url)]
-    url: Option<String>,
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    // Read-only: returned by the API, but not in POST or PUT params
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down_since: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    alias: Option<String>,
-    // Not in POST or PUT params
-    #[serde(skip_serializing)]
-    last_status: Option<u16>,
-    #[serde(skip_serializing)]
-    uptime: Option<f32>,
-    #[serde(skip_serializing)]
-    down: Option<bool>,
-    #[serde(skip_serializing)]
-    down_since: Option<String>,
-    #[serde(skip_serializing)]
-    error: Option<String>,
+    pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[validate(custom = "validate_period")]
-    period: Option<u32>,
+    pub period: Option<Period>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    apdex_t: Option<f32>,
+    pub apdex_t: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    string_match: Option<String>,
+    pub string_match: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    enabled: Option<bool>,
+    pub enabled: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    published: Option<bool>,
+    pub published: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    disabled_locations: Option<Vec<String>>,
+    pub disabled_locations: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    last_check_at: Option<String>,
-    #[serde(skip_serializing)]
-    next_check_at: Option<String>,
+    pub last_check_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    mute_until: Option<String>, //?
+    pub next_check_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mute_until: Option<MuteUntil>,
 
     #[validate(url)]
-    #[serde(skip_serializing)]
-    favicon_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    custom_headers: Option<HashMap<String, String>>,
+    pub favicon_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_headers: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    http_verb: Option<String>,
+    pub http_verb: Option<HttpVerb>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    http_body: Option<String>,
+    pub http_body: Option<String>,
+    /// The ids of the recipients alerted by this check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipients: Option<Vec<String>>,
 
-    #[serde(skip_serializing)]
-    ssl: Option<Ssl>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssl: Option<Ssl>,
 
-    #[serde(skip_serializing)]
-    metrics: Option<Message>,
-}
+    /// The URL a pulse check expects to be pinged at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pulse_url: Option<String>,
 
-fn validate_period(period: u32) -> Result<(), ValidationError> {
-    match period {
-        // 15, 30, 60, 120, 300, 600, 1800 or 3600
-        15 | 30 | 60 | 120 | 300 | 600 | 1800 | 3600 => Ok(()),
-        _ => Err(ValidationError::new(concat!("Invalid value for period"))),
-    }
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<Message>,
 }
 
 /// CheckParams represents the parameters sent to PUT /api/checks:token and POST /api/checks
 #[derive(Clone, Validate, Serialize, Deserialize, Debug, Default, Builder)]
 #[builder(setter(strip_option))]
 pub struct CheckParams {
-    #[serde(rename = "api-key")]
-    api_key: String,
     #[serde(skip_serializing_if = "String::is_empty")]
-    pub(crate) token: String,
+    #[builder(default)]
+    pub token: String,
+    /// Set to [`CheckType::Pulse`] to create a pulse check, which needs no URL
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub check_type: Option<CheckType>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[validate(url)]
     #[builder(default = "None")]
-    url: Option<String>,
+    pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[validate(custom = "validate_period")]
     #[builder(default = "None")]
-    period: Option<u32>,
+    pub period: Option<Period>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    apdex_t: Option<f32>,
+    pub apdex_t: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    string_match: Option<String>,
+    pub string_match: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    enabled: Option<bool>,
+    pub enabled: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    published: Option<bool>,
+    pub published: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    alias: Option<String>,
+    pub alias: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    last_check_at: Option<String>,
+    pub last_check_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    mute_until: Option<String>,
-    //?
+    pub mute_until: Option<MuteUntil>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    http_verb: Option<String>,
+    pub http_verb: Option<HttpVerb>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    http_body: Option<String>,
+    pub http_body: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    disabled_locations: Option<Vec<String>>,
+    pub disabled_locations: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    custom_headers: Option<HashMap<String, String>>,
+    pub custom_headers: Option<HashMap<String, String>>,
+    /// The ids of the recipients to alert, e.g. `email:1234`; see
+    /// [`Client::recipients`](crate::Client::recipients)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub recipients: Option<Vec<String>>,
+}
+
+/// The parameters creating a copy of a check, e.g. in another account. The state of the check,
+/// such as its mute, and its recipients, which belong to its account, are left out.
+impl From<&Check> for CheckParams {
+    fn from(check: &Check) -> CheckParams {
+        CheckParams {
+            check_type: check.check_type.clone(),
+            url: check.url.clone(),
+            period: check.period,
+            apdex_t: check.apdex_t,
+            string_match: check.string_match.clone(),
+            enabled: check.enabled,
+            published: check.published,
+            alias: check.alias.clone(),
+            // Left to updown's default rather than sent back as an unknown method
+            http_verb: check
+                .http_verb
+                .clone()
+                .filter(|verb| !matches!(verb, HttpVerb::Other(_))),
+            http_body: check.http_body.clone(),
+            disabled_locations: check.disabled_locations.clone(),
+            custom_headers: check.custom_headers.clone(),
+            ..Default::default()
+        }
+    }
 }
 
+#[cfg(feature = "cli")]
 impl CheckParams {
-    /// Parses parameters for the update request: PUT /api/checks/:token
-    pub(crate) fn parse_update(
-        api_key: &str,
-        matches: &ArgMatches<'_>,
-    ) -> Result<CheckParams, CliError> {
+    /// Parses parameters for the create request: POST /api/checks
+    pub fn parse_create(matches: &ArgMatches<'_>) -> Result<CheckParams, UpdownError> {
         let mut params = CheckParamsBuilder::default();
-        params.api_key(api_key.to_string());
+        if matches.is_present("pulse") {
+            params.check_type(CheckType::Pulse);
+        } else if !matches.is_present("url") {
+            return Err(UpdownError::BadArg(
+                "a url is required to create a check".to_string(),
+            ));
+        }
         CheckParams::parse(params, matches)
     }
 
-    pub(crate) fn parse(
+    /// Parses parameters for the update request: PUT /api/checks/:token
+    pub fn parse_update(matches: &ArgMatches<'_>) -> Result<CheckParams, UpdownError> {
+        CheckParams::parse(CheckParamsBuilder::default(), matches)
+    }
+
+    pub fn parse(
         mut params: CheckParamsBuilder,
         matches: &ArgMatches<'_>,
-    ) -> Result<CheckParams, CliError> {
+    ) -> Result<CheckParams, UpdownError> {
         let mut parser = Parser::new(matches);
-        let token: String = parser.parse_value("token").unwrap();
-        if !token.is_empty() {
+        if let Some(token) = parser.parse_value("token") {
             params.token(token);
         }
         if let Some(url) = parser.parse_value("url") {
             params.url(url);
         }
+        let mut invalid_period = None;
         if let Some(period) = parser.parse_value("period") {
-            params.period(period);
+            match Period::validated(period) {
+                Ok(period) => {
+                    params.period(period);
+                }
+                Err(e) => invalid_period = Some(e),
+            }
         }
-        if let Some(apdex_t) = parser.parse_value("apdex_t") {
+        if let Some(apdex_t) = parser.parse_value("apdex-t") {
             params.apdex_t(apdex_t);
         }
         if let Some(enabled) = parser.parse_value("enabled") {
@@ -167,46 +485,104 @@ impl CheckParams {
         if let Some(published) = parser.parse_value("published") {
             params.published(published);
         }
-        if let Some(string_match) = parser.parse_value("string_match") {
+        if let Some(string_match) = parser.parse_value("string-match") {
             params.string_match(string_match);
         }
         if let Some(alias) = parser.parse_value("alias") {
             params.alias(alias);
         }
-        if let Some(mute_until) = parser.parse_value("mute_until") {
+        if let Some(mute_until) = parser.parse_value("mute-until") {
             params.mute_until(mute_until);
         }
-        if let Some(http_verb) = parser.parse_value("http_verb") {
+        if let Some(http_verb) = parser.parse_value("http-verb") {
             params.http_verb(http_verb);
         }
-        if let Some(http_body) = parser.parse_value("http_body") {
+        if let Some(http_body) = parser.parse_value("http-body") {
             params.http_body(http_body);
         }
-        if matches.is_present("disabled_locations") {
-            unimplemented!()
-            //params.disabled_locations(matches.value_of("disabled_locations").unwrap().parse().unwrap());
+        if let Some(recipients) = parser.parse_values("recipient") {
+            params.recipients(recipients);
+        }
+        if let Some(locations) = parser.parse_values::<LocationCode>("disabled-location") {
+            params.disabled_locations(locations.into_iter().map(|l| l.0).collect());
+        } else if matches.is_present("clear-disabled-locations") {
+            params.disabled_locations(vec![]);
         }
-        if matches.is_present("custom_headers") {
-            unimplemented!()
-            // params.custom_headers(matches.value_of("custom_headers").unwrap().parse().unwrap());
+        if let Some(headers) = parser.parse_values::<CustomHeader>("header") {
+            params.custom_headers(headers.into_iter().map(|h| (h.name, h.value)).collect());
+        } else if matches.is_present("clear-headers") {
+            params.custom_headers(HashMap::new());
+        }
+        parser.finish()?;
+
+        let params: CheckParams = params.build().map_err(UpdownError::BadArg)?;
+        let mut validation = params.validate();
+        if let Some(period_errors) = invalid_period {
+            let mut errors = validation.err().unwrap_or_default();
+            for (field, field_errors) in period_errors.field_errors() {
+                for error in field_errors {
+                    errors.add(field, error.clone());
+                }
+            }
+            validation = Err(errors);
         }
-        let params: CheckParams = params.build().unwrap();
+        validation?;
+        Ok(params)
+    }
+}
+
+/// A location code given on the command line, e.g. `lan`: three lowercase letters.
+#[cfg(feature = "cli")]
+struct LocationCode(String);
 
-        match params.validate() {
-            Ok(p) => Ok(params),
-            Err(e) => Err(CliError::BadArg("Invalid parameters".to_string()))
+#[cfg(feature = "cli")]
+impl FromStr for LocationCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 3 && s.chars().all(|c| c.is_ascii_lowercase()) {
+            Ok(LocationCode(s.to_string()))
+        } else {
+            Err("must be a location code of 3 lowercase letters, see `updown nodes`".to_string())
         }
     }
 }
 
+/// A custom header given on the command line as `Name: value`.
+#[cfg(feature = "cli")]
+struct CustomHeader {
+    name: String,
+    value: String,
+}
+
+#[cfg(feature = "cli")]
+impl FromStr for CustomHeader {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| "must be given as 'Name: value'".to_string())?;
+        let (name, value) = (name.trim(), value.trim());
+        HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("'{}' is not a valid header name", name))?;
+        HeaderValue::from_str(value)
+            .map_err(|_| format!("'{}' is not a valid header value", value))?;
+        Ok(CustomHeader {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
 #[derive(Clone, Serialize, Validate, Deserialize, Debug)]
 pub struct Ssl {
     #[serde(skip_serializing_if = "Option::is_none")]
-    tested_at: Option<String>,
+    pub tested_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    expires_at: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    valid: Option<bool>,
+    pub valid: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
+    pub error: Option<String>,
 }
@@ -1,55 +1,54 @@
-use crate::command::Parser;
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "cli")]
 use clap::ArgMatches;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use validator::Validate;
 
-/// Downtimes represents the output of /api/checks/:token/downtimes
-/// Possible return values are an array of Downtime messages or an error message.
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(untagged)]
-pub(crate) enum Downtimes {
-    Error { error: Option<String> },
-    Downtimes(Option<Vec<Downtime>>),
-}
-
-#[derive(Serialize, Validate, Deserialize, Debug)]
-pub(crate) struct Downtime {
-    id: Option<String>,
-    error: Option<String>,
-    started_at: Option<String>,
-    ended_at: Option<String>,
-    duration: Option<u64>,
+/// Downtime represents an element of the output of /api/checks/:token/downtimes
+#[derive(Clone, Serialize, Validate, Deserialize, Debug, Default)]
+pub struct Downtime {
+    pub id: Option<String>,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// Missing while the check is still down
+    pub ended_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "crate::messages::seconds")]
+    pub duration: Option<Duration>,
 }
 
 /// DowntimeParams represents the parameters sent to /api/checks/:token/downtimes
 #[derive(Clone, Validate, Serialize, Deserialize, Debug, Default, Builder)]
-#[builder(private, setter(strip_option))]
-pub(crate) struct DowntimeParams<'a> {
-    #[serde(rename = "api-key")]
-    api_key: &'a str,
+#[builder(setter(strip_option))]
+pub struct DowntimeParams<'a> {
     #[serde(skip)]
-    pub(crate) token: &'a str,
+    pub token: &'a str,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    page: Option<u32>,
+    pub page: Option<u32>,
     #[builder(default = "None")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    results: Option<bool>,
+    pub results: Option<bool>,
 }
 
+#[cfg(feature = "cli")]
 impl DowntimeParams<'_> {
-    pub(crate) fn parse<'a>(api_key: &'a str, matches: &'a ArgMatches<'_>) -> DowntimeParams<'a> {
+    pub fn parse<'a>(matches: &'a ArgMatches<'_>) -> Result<DowntimeParams<'a>, UpdownError> {
         let mut params = DowntimeParamsBuilder::default();
         let mut parser = Parser::new(matches);
 
-        params.api_key(api_key);
-        params.token(matches.value_of("token").unwrap());
+        params.token(matches.value_of("token").unwrap_or_default());
         if let Some(page) = parser.parse_value("page") {
             params.page(page);
         }
         if let Some(results) = parser.parse_value("results") {
             params.results(results);
         }
-        params.build().unwrap()
+        parser.finish()?;
+        params.build().map_err(UpdownError::BadArg)
     }
 }
@@ -1,3 +1,11 @@
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+#[cfg(feature = "cli")]
+use crate::time::Time;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "cli")]
 use clap::ArgMatches;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -6,9 +14,9 @@ use validator::Validate;
 /// Metrics represents the output of /api/checks/:token/metrics
 /// Possible return values are a Metric message or an error message.
 ///
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(untagged)]
-pub(crate) enum Metrics {
+pub enum Metrics {
     TimeGroupedMetrics(HashMap<String, Option<Message>>),
     Metrics(Option<Message>),
 }
@@ -16,76 +24,82 @@ pub(crate) enum Metrics {
 #[derive(Clone, Serialize, Validate, Deserialize, Debug)]
 pub struct Message {
     #[serde(skip_serializing_if = "Option::is_none")]
-    apdex: Option<f32>,
-    requests: Option<Requests>,
-    timings: Option<Timings>,
+    pub apdex: Option<f32>,
+    pub requests: Option<Requests>,
+    pub timings: Option<Timings>,
 }
 
 #[derive(Clone, Serialize, Validate, Deserialize, Debug)]
 pub struct Requests {
     #[serde(skip_serializing_if = "Option::is_none")]
-    samples: Option<u32>,
-    failures: Option<u32>,
-    satisfied: Option<u32>,
-    tolerated: Option<u32>,
-    by_response_time: Option<ResponseTimes>,
-    timings: Option<Timings>,
+    pub samples: Option<u32>,
+    pub failures: Option<u32>,
+    pub satisfied: Option<u32>,
+    pub tolerated: Option<u32>,
+    pub by_response_time: Option<ResponseTimes>,
+    pub timings: Option<Timings>,
 }
 
 #[derive(Clone, Serialize, Validate, Deserialize, Debug)]
 pub struct ResponseTimes {
-    under125: Option<u32>,
-    under250: Option<u32>,
-    under500: Option<u32>,
-    under1000: Option<u32>,
-    under2000: Option<u32>,
-    under4000: Option<u32>,
+    pub under125: Option<u32>,
+    pub under250: Option<u32>,
+    pub under500: Option<u32>,
+    pub under1000: Option<u32>,
+    pub under2000: Option<u32>,
+    pub under4000: Option<u32>,
 }
 
 #[derive(Clone, Serialize, Validate, Deserialize, Debug)]
 pub struct Timings {
-    redirect: Option<u32>,
-    namelookup: Option<u32>,
-    connection: Option<u32>,
-    handshake: Option<u32>,
-    response: Option<u32>,
-    total: Option<u32>,
+    pub redirect: Option<u32>,
+    pub namelookup: Option<u32>,
+    pub connection: Option<u32>,
+    pub handshake: Option<u32>,
+    pub response: Option<u32>,
+    pub total: Option<u32>,
 }
 
 #[derive(Clone, Validate, Serialize, Deserialize, Debug, Default, Builder)]
 #[builder(setter(strip_option))]
-pub(crate) struct MetricsParams<'a> {
-    #[serde(rename = "api-key")]
-    pub(crate) api_key: &'a str,
+pub struct MetricsParams<'a> {
     #[serde(skip)]
-    pub(crate) token: &'a str,
+    pub token: &'a str,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    from: Option<&'a str>,
+    pub from: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    to: Option<&'a str>,
+    pub to: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default = "None")]
-    pub(crate) group: Option<&'a str>,
+    pub group: Option<&'a str>,
 }
 
+#[cfg(feature = "cli")]
 impl MetricsParams<'_> {
-    pub(crate) fn parse<'a>(api_key: &'a str, matches: &'a ArgMatches<'a>) -> MetricsParams<'a> {
+    pub fn parse<'a>(matches: &'a ArgMatches<'a>) -> Result<MetricsParams<'a>, UpdownError> {
         let mut params = MetricsParamsBuilder::default();
-        params.api_key(api_key);
-        params.token(matches.value_of("token").unwrap());
-        if let Some(from) = matches.value_of("from") {
+        let mut parser = Parser::new(matches);
+        params.token(matches.value_of("token").unwrap_or_default());
+        if let Some(Time(from)) = parser.parse_value("from") {
             params.from(from);
         }
-
-        if let Some(to) = matches.value_of("to") {
+        if let Some(Time(to)) = parser.parse_value("to") {
             params.to(to);
         }
-
         if let Some(group) = matches.value_of("group") {
             params.group(group);
         }
-        params.build().unwrap()
+        if let (Some(Some(from)), Some(Some(to))) = (&params.from, &params.to) {
+            if from > to {
+                parser.reject(
+                    "from",
+                    &format!("{} is after --to {}", from.to_rfc3339(), to.to_rfc3339()),
+                );
+            }
+        }
+        parser.finish()?;
+        params.build().map_err(UpdownError::BadArg)
     }
 }
@@ -1,17 +1,44 @@
-use crate::command::CliError;
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
 
-pub(crate) mod check;
-pub(crate) mod downtime;
-pub(crate) mod metric;
+pub mod check;
+pub mod downtime;
+pub mod metric;
+pub mod node;
+pub mod pulse;
+pub mod recipient;
+pub mod status_page;
+pub mod webhook;
 
-quick_error! {
+/// Deleted represents the output of the DELETE requests, e.g. DELETE /api/checks/:token
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Deleted {
+    pub deleted: bool,
+}
+
+/// Serializes durations as a number of seconds, the way the API gives them.
+pub(crate) mod seconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.as_secs()),
+            None => serializer.serialize_none(),
+        }
+    }
 
-    /// Error specific to updown
-    #[derive(Debug)]
-    pub enum MessageError {
-        RequestFailed( cause : reqwest::Error){from()}
-        JsonFailed( cause : serde_json::Error){from()}
-        CommandFailed(cause : CliError) {display("{}", cause.to_string())}
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
     }
 }
+
+/// ErrorMessage represents the body of an error response
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct ErrorMessage {
+    pub(crate) error: Option<String>,
+}
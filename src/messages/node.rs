@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Nodes represents the output of /api/nodes, keyed by location code (e.g. `lan`, `mia`). These
+/// codes are the ones listed in `Check::disabled_locations`.
+pub type Nodes = BTreeMap<String, Node>;
+
+/// Node represents a monitoring location of updown.io
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Node {
+    pub ip: Option<String>,
+    pub ip6: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+}
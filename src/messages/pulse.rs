@@ -0,0 +1,38 @@
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+#[cfg(feature = "cli")]
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+
+/// PulseParams represents the optional report sent with a ping to the URL of a pulse check
+#[derive(Clone, Serialize, Deserialize, Debug, Default, Builder)]
+#[builder(setter(strip_option))]
+pub struct PulseParams {
+    /// The exit code of the job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub exit_code: Option<i32>,
+    /// How long the job ran, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub duration: Option<f64>,
+}
+
+#[cfg(feature = "cli")]
+impl PulseParams {
+    pub fn parse(matches: &ArgMatches<'_>) -> Result<PulseParams, UpdownError> {
+        let mut params = PulseParamsBuilder::default();
+        let mut parser = Parser::new(matches);
+
+        if let Some(exit_code) = parser.parse_value("exit-code") {
+            params.exit_code(exit_code);
+        }
+        if let Some(duration) = parser.parse_value("duration") {
+            params.duration(duration);
+        }
+        parser.finish()?;
+        params.build().map_err(UpdownError::BadArg)
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+#[cfg(feature = "cli")]
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// RecipientType is the channel an alert recipient is notified through
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipientType {
+    Email,
+    Sms,
+    #[serde(rename = "slack_compatible")]
+    Slack,
+    Webhook,
+    /// A channel this crate doesn't know about yet
+    #[serde(other)]
+    Other,
+}
+
+impl fmt::Display for RecipientType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RecipientType::Email => "email",
+            RecipientType::Sms => "sms",
+            RecipientType::Slack => "slack_compatible",
+            RecipientType::Webhook => "webhook",
+            RecipientType::Other => "other",
+        })
+    }
+}
+
+impl FromStr for RecipientType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "email" => Ok(RecipientType::Email),
+            "sms" => Ok(RecipientType::Sms),
+            "slack" | "slack_compatible" => Ok(RecipientType::Slack),
+            "webhook" => Ok(RecipientType::Webhook),
+            _ => Err("must be one of email, sms, slack or webhook".to_string()),
+        }
+    }
+}
+
+/// Recipient represents an element of the output of /api/recipients
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Recipient {
+    /// e.g. `email:1234`, the value given in `CheckParams::recipients`
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: RecipientType,
+    pub name: Option<String>,
+    /// The email address, phone number or URL notified
+    pub value: Option<String>,
+}
+
+/// RecipientParams represents the parameters sent to POST /api/recipients
+#[derive(Clone, Serialize, Deserialize, Debug, Builder)]
+#[builder(setter(strip_option))]
+pub struct RecipientParams {
+    #[serde(rename = "type")]
+    pub kind: RecipientType,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub name: Option<String>,
+    /// Whether new checks notify this recipient by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub selected: Option<bool>,
+}
+
+#[cfg(feature = "cli")]
+impl RecipientParams {
+    pub fn parse(matches: &ArgMatches<'_>) -> Result<RecipientParams, UpdownError> {
+        let mut params = RecipientParamsBuilder::default();
+        let mut parser = Parser::new(matches);
+
+        if let Some(kind) = parser.parse_value("type") {
+            params.kind(kind);
+        }
+        if let Some(value) = parser.parse_value("value") {
+            params.value(value);
+        }
+        if let Some(name) = parser.parse_value("name") {
+            params.name(name);
+        }
+        if matches.is_present("selected") {
+            params.selected(true);
+        }
+        parser.finish()?;
+        params.build().map_err(UpdownError::BadArg)
+    }
+}
@@ -0,0 +1,135 @@
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+#[cfg(feature = "cli")]
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Visibility controls who can see a status page
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum Visibility {
+    Public,
+    /// Visible with the access key
+    Protected,
+    /// Visible to the account owner only
+    Private,
+    /// A visibility this crate doesn't know about yet, as the API named it
+    Other(String),
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Visibility::Public => "public",
+            Visibility::Protected => "protected",
+            Visibility::Private => "private",
+            Visibility::Other(name) => name,
+        })
+    }
+}
+
+/// Any name is accepted, so that a new visibility doesn't make the status pages unreadable.
+impl From<String> for Visibility {
+    fn from(name: String) -> Visibility {
+        match name.as_str() {
+            "public" => Visibility::Public,
+            "protected" => Visibility::Protected,
+            "private" => Visibility::Private,
+            _ => Visibility::Other(name),
+        }
+    }
+}
+
+impl From<Visibility> for String {
+    fn from(visibility: Visibility) -> String {
+        visibility.to_string()
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(Visibility::Public),
+            "protected" => Ok(Visibility::Protected),
+            "private" => Ok(Visibility::Private),
+            _ => Err("must be one of public, protected or private".to_string()),
+        }
+    }
+}
+
+/// StatusPage represents the output of /api/status_pages/:token; Vec<StatusPage> is deserialized
+/// from /api/status_pages
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct StatusPage {
+    pub token: Option<String>,
+    pub url: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub visibility: Option<Visibility>,
+    pub access_key: Option<String>,
+    /// The tokens of the checks shown on the page
+    pub checks: Option<Vec<String>>,
+}
+
+/// StatusPageParams represents the parameters sent to POST /api/status_pages and
+/// PUT /api/status_pages/:token
+#[derive(Clone, Serialize, Deserialize, Debug, Default, Builder)]
+#[builder(setter(strip_option))]
+pub struct StatusPageParams {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[builder(default)]
+    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub visibility: Option<Visibility>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub access_key: Option<String>,
+    /// The tokens of the checks to show on the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default = "None")]
+    pub checks: Option<Vec<String>>,
+}
+
+#[cfg(feature = "cli")]
+impl StatusPageParams {
+    /// Parses the parameters of the status-pages add and update subcommands. Checks are kept as
+    /// given, tokens or aliases, and are resolved to tokens by the command.
+    pub fn parse(matches: &ArgMatches<'_>) -> Result<StatusPageParams, UpdownError> {
+        let mut params = StatusPageParamsBuilder::default();
+        let mut parser = Parser::new(matches);
+
+        if let Some(token) = parser.parse_value("token") {
+            params.token(token);
+        }
+        if let Some(name) = parser.parse_value("name") {
+            params.name(name);
+        }
+        if let Some(description) = parser.parse_value("description") {
+            params.description(description);
+        }
+        if let Some(visibility) = parser.parse_value("visibility") {
+            params.visibility(visibility);
+        }
+        if let Some(access_key) = parser.parse_value("access-key") {
+            params.access_key(access_key);
+        }
+        if let Some(checks) = parser.parse_values("check") {
+            params.checks(checks);
+        }
+        parser.finish()?;
+        params.build().map_err(UpdownError::BadArg)
+    }
+}
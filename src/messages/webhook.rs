@@ -0,0 +1,38 @@
+#[cfg(feature = "cli")]
+use crate::parser::Parser;
+#[cfg(feature = "cli")]
+use crate::UpdownError;
+#[cfg(feature = "cli")]
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Webhook represents an element of the output of /api/webhooks
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Webhook {
+    pub id: Option<String>,
+    pub url: Option<String>,
+}
+
+/// WebhookParams represents the parameters sent to POST /api/webhooks
+#[derive(Clone, Validate, Serialize, Deserialize, Debug, Default, Builder)]
+pub struct WebhookParams {
+    #[validate(url)]
+    pub url: String,
+}
+
+#[cfg(feature = "cli")]
+impl WebhookParams {
+    pub fn parse(matches: &ArgMatches<'_>) -> Result<WebhookParams, UpdownError> {
+        let mut params = WebhookParamsBuilder::default();
+        let mut parser = Parser::new(matches);
+
+        if let Some(url) = parser.parse_value("url") {
+            params.url(url);
+        }
+        parser.finish()?;
+        let params = params.build().map_err(UpdownError::BadArg)?;
+        params.validate()?;
+        Ok(params)
+    }
+}
@@ -0,0 +1,570 @@
+//! Formats for the CLI output, chosen with the global `--output` flag.
+//!
+//! `json` (the default), `json-pretty` and `yaml` serialize the response as is. `table` and `csv`
+//! lay it out as rows and columns through [`Tabular`]: checks and downtimes get hand-picked
+//! columns, any other response is flattened from its JSON form.
+
+use crate::manifest::{Action, Change};
+use crate::messages::check::{Check, MuteUntil};
+use crate::messages::downtime::Downtime;
+use crate::UpdownError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    JsonPretty,
+    Table,
+    Yaml,
+    Csv,
+}
+
+impl Format {
+    pub const NAMES: [&'static str; 5] = ["json", "json-pretty", "table", "yaml", "csv"];
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Json => "json",
+            Format::JsonPretty => "json-pretty",
+            Format::Table => "table",
+            Format::Yaml => "yaml",
+            Format::Csv => "csv",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "json-pretty" => Ok(Format::JsonPretty),
+            "table" => Ok(Format::Table),
+            "yaml" => Ok(Format::Yaml),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("must be one of {}", Format::NAMES.join(", "))),
+        }
+    }
+}
+
+/// Renders `value` in the given format. Table cells are coloured only when `color` is set, which
+/// the CLI does when writing to a terminal.
+pub fn render<T: Tabular>(value: &T, format: Format, color: bool) -> Result<String, UpdownError> {
+    Ok(match format {
+        Format::Json => serde_json::to_string(value)?,
+        Format::JsonPretty => serde_json::to_string_pretty(value)?,
+        Format::Yaml => serde_yaml::to_string(value)?.trim_end().to_string(),
+        Format::Table => value.table()?.render(color),
+        Format::Csv => value.table()?.csv()?,
+    })
+}
+
+/// Tabular is a response that can be shown as a table. The default layout is built from the JSON
+/// form of the value.
+pub trait Tabular: Serialize {
+    fn table(&self) -> Result<Table, UpdownError> {
+        Ok(Table::from_value(&serde_json::to_value(self)?))
+    }
+}
+
+impl<T: Tabular> Tabular for &T {
+    fn table(&self) -> Result<Table, UpdownError> {
+        (*self).table()
+    }
+}
+
+impl Tabular for Vec<Check> {
+    fn table(&self) -> Result<Table, UpdownError> {
+        let mut table = Table::new(&[
+            "token",
+            "alias",
+            "url",
+            "status",
+            "uptime",
+            "period",
+            "last check",
+        ]);
+        for check in self {
+            table.push(vec![
+                Cell::from(&check.token),
+                Cell::from(&check.alias),
+                Cell::from(&check.url),
+                status(check),
+                Cell::from(check.uptime.map(|uptime| format!("{:.2}%", uptime))),
+                Cell::from(check.period.map(|period| human(period.as_duration()))),
+                Cell::from(check.last_check_at.map(timestamp)),
+            ]);
+        }
+        Ok(table)
+    }
+}
+
+impl Tabular for Check {
+    fn table(&self) -> Result<Table, UpdownError> {
+        let mut table = Table::key_value();
+        table.pair("token", Cell::from(&self.token));
+        table.pair(
+            "type",
+            Cell::from(self.check_type.as_ref().map(|t| t.to_string())),
+        );
+        table.pair("alias", Cell::from(&self.alias));
+        table.pair("url", Cell::from(&self.url));
+        table.pair("pulse url", Cell::from(&self.pulse_url));
+        table.pair("status", status(self));
+        table.pair(
+            "last status",
+            Cell::from(self.last_status.map(|s| s.to_string())),
+        );
+        table.pair("error", Cell::from(&self.error));
+        table.pair("down since", Cell::from(self.down_since.map(timestamp)));
+        table.pair(
+            "uptime",
+            Cell::from(self.uptime.map(|uptime| format!("{:.2}%", uptime))),
+        );
+        table.pair(
+            "period",
+            Cell::from(self.period.map(|period| human(period.as_duration()))),
+        );
+        table.pair("apdex t", Cell::from(self.apdex_t.map(|t| t.to_string())));
+        table.pair(
+            "http verb",
+            Cell::from(self.http_verb.as_ref().map(|v| v.to_string())),
+        );
+        table.pair("string match", Cell::from(&self.string_match));
+        table.pair("published", Cell::from(self.published.map(yes_no)));
+        table.pair("last check", Cell::from(self.last_check_at.map(timestamp)));
+        table.pair("next check", Cell::from(self.next_check_at.map(timestamp)));
+        table.pair(
+            "mute until",
+            Cell::from(self.mute_until.map(|mute_until| match mute_until {
+                MuteUntil::Time(time) => timestamp(time),
+                MuteUntil::Forever => "forever".to_string(),
+                MuteUntil::Unmuted => String::new(),
+            })),
+        );
+        table.pair(
+            "ssl expires",
+            Cell::from(
+                self.ssl
+                    .as_ref()
+                    .and_then(|ssl| ssl.expires_at)
+                    .map(timestamp),
+            ),
+        );
+        table.pair(
+            "disabled locations",
+            Cell::from(self.disabled_locations.as_ref().map(|l| l.join(", "))),
+        );
+        table.pair(
+            "recipients",
+            Cell::from(self.recipients.as_ref().map(|r| r.join(", "))),
+        );
+        Ok(table)
+    }
+}
+
+impl Tabular for Vec<Downtime> {
+    fn table(&self) -> Result<Table, UpdownError> {
+        let mut table = Table::new(&["started", "ended", "duration", "error"]);
+        for downtime in self {
+            let ended = match downtime.ended_at {
+                Some(ended_at) => Cell::plain(timestamp(ended_at)),
+                None => Cell::colored("ongoing".to_string(), Color::Red),
+            };
+            table.push(vec![
+                Cell::from(downtime.started_at.map(timestamp)),
+                ended,
+                Cell::from(downtime.duration.map(human)),
+                Cell::from(&downtime.error),
+            ]);
+        }
+        Ok(table)
+    }
+}
+
+impl Tabular for Vec<Change> {
+    fn table(&self) -> Result<Table, UpdownError> {
+        let mut table = Table::new(&["action", "check", "token", "fields"]);
+        for change in self {
+            let name = action(change.action).to_string();
+            let action = match change.action {
+                Action::Create => Cell::colored(name, Color::Green),
+                Action::Update => Cell::plain(name),
+                Action::Delete => Cell::colored(name, Color::Red),
+            };
+            let fields: Vec<&str> = change.diff.iter().map(|d| d.field.as_str()).collect();
+            table.push(vec![
+                action,
+                Cell::plain(change.check.clone()),
+                Cell::from(&change.token),
+                Cell::plain(fields.join(", ")),
+            ]);
+        }
+        Ok(table)
+    }
+}
+
+impl Tabular for Value {}
+impl Tabular for Vec<String> {}
+impl Tabular for Vec<crate::bulk::Outcome> {}
+impl Tabular for Vec<crate::config::Profile> {}
+impl Tabular for crate::messages::Deleted {}
+impl Tabular for crate::messages::metric::Metrics {}
+impl Tabular for crate::messages::node::Nodes {}
+impl Tabular for crate::messages::recipient::Recipient {}
+impl Tabular for Vec<crate::messages::recipient::Recipient> {}
+impl Tabular for crate::messages::status_page::StatusPage {}
+impl Tabular for Vec<crate::messages::status_page::StatusPage> {}
+impl Tabular for crate::messages::webhook::Webhook {}
+impl Tabular for Vec<crate::messages::webhook::Webhook> {}
+
+/// Shows changes as a unified diff, with a hunk per check: `-` for the values the check has, `+`
+/// for the values it would get.
+pub fn unified_diff(changes: &[Change], color: bool) -> String {
+    let mut lines = vec![];
+    for change in changes {
+        let token = change
+            .token
+            .as_ref()
+            .map(|token| format!(" ({})", token))
+            .unwrap_or_default();
+        let header = format!("@@ {} {}{} @@", action(change.action), change.check, token);
+        lines.push(paint(&Cell::colored(header, Color::Cyan), color));
+        for diff in &change.diff {
+            if !diff.old.is_null() {
+                let line = format!("-{}: {}", diff.field, diff.old);
+                lines.push(paint(&Cell::colored(line, Color::Red), color));
+            }
+            if !diff.new.is_null() {
+                let line = format!("+{}: {}", diff.field, diff.new);
+                lines.push(paint(&Cell::colored(line, Color::Green), color));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Shows changes as a JSON patch (RFC 6902) of the checks, keyed by alias or URL.
+pub fn json_patch(changes: &[Change]) -> Value {
+    let mut operations = vec![];
+    for change in changes {
+        let path = format!("/{}", pointer(&change.check));
+        match change.action {
+            Action::Create => {
+                let fields: serde_json::Map<String, Value> = change
+                    .diff
+                    .iter()
+                    .map(|diff| (diff.field.clone(), diff.new.clone()))
+                    .collect();
+                operations.push(json!({"op": "add", "path": path, "value": fields}));
+            }
+            Action::Update => {
+                for diff in &change.diff {
+                    let path = format!("{}/{}", path, pointer(&diff.field));
+                    operations.push(match (&diff.old, &diff.new) {
+                        (_, Value::Null) => json!({"op": "remove", "path": path}),
+                        (Value::Null, new) => json!({"op": "add", "path": path, "value": new}),
+                        (_, new) => json!({"op": "replace", "path": path, "value": new}),
+                    });
+                }
+            }
+            Action::Delete => operations.push(json!({"op": "remove", "path": path})),
+        }
+    }
+    Value::Array(operations)
+}
+
+/// Escapes a JSON pointer segment (RFC 6901).
+fn pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn action(action: Action) -> &'static str {
+    match action {
+        Action::Create => "create",
+        Action::Update => "update",
+        Action::Delete => "delete",
+    }
+}
+
+fn status(check: &Check) -> Cell {
+    match (check.enabled, check.down) {
+        (Some(false), _) => Cell::plain("disabled".to_string()),
+        (_, Some(true)) => Cell::colored("down".to_string(), Color::Red),
+        (_, Some(false)) => Cell::colored("up".to_string(), Color::Green),
+        _ => Cell::default(),
+    }
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+/// Formats a duration with its two largest units, e.g. `14m 27s` or `2d 3h`.
+pub fn human(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let parts: Vec<String> = [
+        (secs / 86400, "d"),
+        (secs / 3600 % 24, "h"),
+        (secs / 60 % 60, "m"),
+        (secs % 60, "s"),
+    ]
+    .iter()
+    .skip_while(|(amount, _)| *amount == 0)
+    .take(2)
+    .filter(|(amount, _)| *amount > 0)
+    .map(|(amount, unit)| format!("{}{}", amount, unit))
+    .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Cyan,
+    Green,
+    Red,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Color::Cyan => "36",
+            Color::Green => "32",
+            Color::Red => "31",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cell {
+    pub text: String,
+    pub color: Option<Color>,
+}
+
+impl Cell {
+    pub fn plain(text: String) -> Cell {
+        Cell { text, color: None }
+    }
+
+    pub fn colored(text: String, color: Color) -> Cell {
+        Cell {
+            text,
+            color: Some(color),
+        }
+    }
+}
+
+impl From<Option<String>> for Cell {
+    fn from(text: Option<String>) -> Cell {
+        Cell::plain(text.unwrap_or_default())
+    }
+}
+
+impl From<&Option<String>> for Cell {
+    fn from(text: &Option<String>) -> Cell {
+        Cell::plain(text.clone().unwrap_or_default())
+    }
+}
+
+/// Table is either rows under a header, or a key/value list with one field per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+    pub key_value: bool,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Table {
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn key_value() -> Table {
+        Table {
+            key_value: true,
+            ..Table::new(&["field", "value"])
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// Adds a field to a key/value table, leaving out empty values.
+    pub fn pair(&mut self, key: &str, value: Cell) {
+        if !value.text.is_empty() {
+            self.push(vec![Cell::plain(key.to_string()), value]);
+        }
+    }
+
+    /// Flattens a JSON value: an array of objects gives a column per field, an object of objects
+    /// (such as the nodes) a row per key, and any other object a key/value list.
+    pub fn from_value(value: &Value) -> Table {
+        match value {
+            Value::Array(items) if items.iter().all(Value::is_object) => {
+                Table::from_objects(None, items.iter().map(|item| (None, item)))
+            }
+            Value::Array(items) => {
+                let mut table = Table::new(&["value"]);
+                for item in items {
+                    table.push(vec![Cell::plain(text(item))]);
+                }
+                table
+            }
+            Value::Object(fields)
+                if !fields.is_empty() && fields.values().all(Value::is_object) =>
+            {
+                Table::from_objects(
+                    Some("key"),
+                    fields.iter().map(|(key, item)| (Some(key.as_str()), item)),
+                )
+            }
+            Value::Object(fields) => {
+                let mut table = Table::key_value();
+                for (key, value) in fields {
+                    table.pair(key, Cell::plain(text(value)));
+                }
+                table
+            }
+            value => {
+                let mut table = Table::new(&["value"]);
+                table.push(vec![Cell::plain(text(value))]);
+                table
+            }
+        }
+    }
+
+    fn from_objects<'a>(
+        key_header: Option<&str>,
+        items: impl Iterator<Item = (Option<&'a str>, &'a Value)> + Clone,
+    ) -> Table {
+        let mut columns: Vec<&str> = Vec::new();
+        for (_, item) in items.clone() {
+            for field in item
+                .as_object()
+                .into_iter()
+                .flat_map(|fields| fields.keys())
+            {
+                if !columns.contains(&field.as_str()) {
+                    columns.push(field);
+                }
+            }
+        }
+        let mut headers: Vec<&str> = key_header.into_iter().collect();
+        headers.extend(&columns);
+        let mut table = Table::new(&headers);
+        for (key, item) in items {
+            let mut row: Vec<Cell> = key
+                .map(|k| Cell::plain(k.to_string()))
+                .into_iter()
+                .collect();
+            row.extend(
+                columns
+                    .iter()
+                    .map(|column| Cell::plain(item.get(column).map(text).unwrap_or_default())),
+            );
+            table.push(row);
+        }
+        table
+    }
+
+    /// Aligns the columns, two spaces apart.
+    pub fn render(&self, color: bool) -> String {
+        let mut lines = Vec::new();
+        if self.key_value {
+            let width = self.rows.iter().map(|row| width(&row[0].text)).max();
+            for row in &self.rows {
+                let key = format!("{}:", row[0].text);
+                let value = paint(&row[1], color);
+                lines.push(format!("{:w$}  {}", key, value, w = width.unwrap_or(0) + 1));
+            }
+            return lines.join("\n");
+        }
+        let widths: Vec<usize> = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| width(&cell.text))
+                    .chain(std::iter::once(width(header)))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let header: Vec<Cell> = self
+            .headers
+            .iter()
+            .map(|h| Cell::plain(h.to_uppercase()))
+            .collect();
+        for row in std::iter::once(&header).chain(&self.rows) {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| {
+                    let padding = " ".repeat(width - self::width(&cell.text));
+                    format!("{}{}", paint(cell, color), padding)
+                })
+                .collect();
+            lines.push(line.join("  ").trim_end().to_string());
+        }
+        lines.join("\n")
+    }
+
+    pub fn csv(&self) -> Result<String, UpdownError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(&self.headers)?;
+        for row in &self.rows {
+            writer.write_record(row.iter().map(|cell| &cell.text))?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| csv::Error::from(e.into_error()))?;
+        Ok(String::from_utf8_lossy(&bytes).trim_end().to_string())
+    }
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn paint(cell: &Cell, color: bool) -> String {
+    match cell.color {
+        Some(c) if color => format!("\x1b[{}m{}\x1b[0m", c.code(), cell.text),
+        _ => cell.text.clone(),
+    }
+}
+
+/// The text of a JSON value in a cell: strings unquoted, lists comma-separated and nothing for
+/// null.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(text).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,72 @@
+//! Reads the values of command-line options for the `parse` functions of the messages and for
+//! the `updown` binary. It is tied to clap and isn't part of the supported API.
+
+use crate::UpdownError;
+use clap::ArgMatches;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+/// Parser reads typed values from the matches of a subcommand, collecting every value that
+/// fails to parse so they can all be reported at once.
+pub struct Parser<'a> {
+    pub matches: &'a ArgMatches<'a>,
+    pub parse_errors: Vec<String>,
+    pub successful_parse: bool,
+}
+
+impl Parser<'_> {
+    pub fn new<'a>(matches: &'a ArgMatches<'_>) -> Parser<'a> {
+        Parser {
+            matches,
+            parse_errors: vec![],
+            successful_parse: true,
+        }
+    }
+
+    pub fn parse_value<T>(&mut self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Debug + Display,
+    {
+        let value = self.matches.value_of(key)?;
+        match value.parse::<T>() {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.reject(key, &format!("{} ({} given)", e, value));
+                None
+            }
+        }
+    }
+
+    /// Parses every value given to a repeatable option.
+    pub fn parse_values<T>(&mut self, key: &str) -> Option<Vec<T>>
+    where
+        T: FromStr,
+        T::Err: Debug + Display,
+    {
+        let values = self.matches.values_of(key)?;
+        let mut parsed = vec![];
+        for value in values {
+            match value.parse::<T>() {
+                Ok(v) => parsed.push(v),
+                Err(e) => self.reject(key, &format!("{} ({} given)", e, value)),
+            }
+        }
+        Some(parsed)
+    }
+
+    /// Records a value as invalid.
+    pub fn reject(&mut self, key: &str, reason: &str) {
+        self.parse_errors.push(format!("--{}: {}", key, reason));
+        self.successful_parse = false;
+    }
+
+    /// Fails with every parse error recorded so far.
+    pub fn finish(&self) -> Result<(), UpdownError> {
+        if self.successful_parse {
+            Ok(())
+        } else {
+            Err(UpdownError::BadArg(self.parse_errors.join(", ")))
+        }
+    }
+}
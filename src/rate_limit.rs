@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::time::delay_for;
+
+use crate::UpdownError;
+
+/// The lowest rate accepted, one request every 1000 seconds.
+pub const MIN_REQUESTS_PER_SECOND: f64 = 0.001;
+
+/// Why a rate is rejected, for the library and the `config` command alike.
+pub const RATE_REASON: &str = "must be a number of at least 0.001";
+
+/// The longest a request waits for its turn, however many are queued before it.
+const MAX_WAIT: Duration = Duration::from_secs(3600);
+
+/// RateLimiter is a token bucket holding up to `burst` requests, refilled at `requests_per_second`.
+///
+/// Clones share the same bucket, so every clone of a [`Client`](crate::Client), e.g. one per task
+/// spawned on the tokio runtime, draws from one budget.
+///
+/// ```
+/// use updown::client::ClientBuilder;
+/// use updown::rate_limit::RateLimiter;
+///
+/// let client = ClientBuilder::default()
+///     .api_key("your-api-key")
+///     .rate_limiter(RateLimiter::new(5.0, 10).unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    requests_per_second: f64,
+    burst: f64,
+    /// Goes below zero when requests are queued waiting for their turn.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// Allows `requests_per_second` on average, and up to `burst` requests at once. The rate must
+    /// be a finite number of at least [`MIN_REQUESTS_PER_SECOND`].
+    pub fn new(requests_per_second: f64, burst: u32) -> Result<RateLimiter, UpdownError> {
+        if !requests_per_second.is_finite() || requests_per_second < MIN_REQUESTS_PER_SECOND {
+            return Err(UpdownError::BadArg(format!(
+                "requests per second: {} ({} given)",
+                RATE_REASON, requests_per_second
+            )));
+        }
+        let burst = f64::from(burst.max(1));
+        Ok(RateLimiter {
+            bucket: Arc::new(Mutex::new(Bucket {
+                requests_per_second,
+                burst,
+                tokens: burst,
+                refilled_at: Instant::now(),
+            })),
+        })
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        let wait = self.reserve();
+        if wait > Duration::from_secs(0) {
+            delay_for(wait).await;
+        }
+    }
+
+    /// Takes a token, returning how long to wait for it if the bucket is empty. Callers that
+    /// have to wait are served in the order they reserved.
+    fn reserve(&self) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * bucket.requests_per_second).min(bucket.burst);
+        bucket.refilled_at = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            let wait = -bucket.tokens / bucket.requests_per_second;
+            Duration::from_secs_f64(wait.min(MAX_WAIT.as_secs_f64()))
+        }
+    }
+}
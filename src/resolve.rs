@@ -0,0 +1,111 @@
+//! Finds checks from what a user would remember of them: a token, an alias or a URL.
+
+use crate::messages::check::Check;
+use crate::UpdownError;
+
+/// Finds the check named by `reference`, which is tried in turn as:
+///
+/// 1. a token
+/// 2. an alias or a URL, a trailing `/` aside
+/// 3. a pattern for aliases and URLs, when it holds `*` (any characters) or `?` (one character),
+///    e.g. `https://*.example.com/*`
+///
+/// Returns `None` when no check matches, and an error listing the candidates when several do.
+pub fn find<'a>(checks: &'a [Check], reference: &str) -> Result<Option<&'a Check>, UpdownError> {
+    if let Some(check) = checks
+        .iter()
+        .find(|c| c.token.as_deref() == Some(reference))
+    {
+        return Ok(Some(check));
+    }
+    let mut candidates: Vec<&Check> = checks
+        .iter()
+        .filter(|c| c.alias.as_deref() == Some(reference) || same_url(c.url.as_deref(), reference))
+        .collect();
+    if candidates.is_empty() && is_pattern(reference) {
+        candidates = checks
+            .iter()
+            .filter(|c| {
+                c.alias
+                    .iter()
+                    .chain(&c.url)
+                    .any(|text| glob(reference, text))
+            })
+            .collect();
+    }
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [check] => Ok(Some(check)),
+        _ => Err(UpdownError::BadArg(format!(
+            "{} matches several checks, use one of the tokens: {}",
+            reference,
+            candidates
+                .iter()
+                .map(|c| describe(c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// Whether `reference` could be a token, which is made of letters and digits. Aliases can look
+/// like tokens too, so such a reference may still have to be searched for.
+pub fn may_be_token(reference: &str) -> bool {
+    !reference.is_empty() && reference.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Whether `reference` holds wildcards.
+pub fn is_pattern(reference: &str) -> bool {
+    reference.contains('*') || reference.contains('?')
+}
+
+/// Matches `text` against a pattern where `*` is any run of characters and `?` one character.
+pub fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // The position after the last `*`, and the text position it has been matched up to
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((after, matched)) => {
+                    star = Some((after, matched + 1));
+                    p = after;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether a check has the URL `reference`, a trailing `/` aside. Manifests match checks the same
+/// way.
+pub fn same_url(url: Option<&str>, reference: &str) -> bool {
+    url.is_some_and(|url| url.trim_end_matches('/') == reference.trim_end_matches('/'))
+}
+
+/// e.g. `aaaa (shop, https://shop.example.com)`
+fn describe(check: &Check) -> String {
+    let names: Vec<&str> = check
+        .alias
+        .iter()
+        .chain(&check.url)
+        .map(String::as_str)
+        .collect();
+    format!(
+        "{} ({})",
+        check.token.as_deref().unwrap_or_default(),
+        names.join(", ")
+    )
+}
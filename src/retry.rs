@@ -0,0 +1,123 @@
+use std::time::{Duration, SystemTime};
+
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Method, Response};
+
+/// RetryPolicy decides whether, and after how long, the [`Client`](crate::Client) replays a
+/// request that failed with a retryable HTTP status or a network error.
+///
+/// The delay doubles with each attempt, starting from `base_delay` and capped at `max_delay`, and
+/// is shortened by a random fraction (up to `jitter`) so concurrent clients don't retry in
+/// lockstep. A `Retry-After` header sent by the API takes precedence over the computed delay.
+///
+/// Only GET requests are retried by default: replaying a POST, PUT or DELETE whose response was
+/// lost could apply it twice.
+///
+/// ```
+/// use std::time::Duration;
+/// use updown::client::ClientBuilder;
+/// use updown::retry::RetryPolicyBuilder;
+///
+/// let policy = RetryPolicyBuilder::default()
+///     .max_attempts(5u32)
+///     .base_delay(Duration::from_millis(200))
+///     .build()
+///     .unwrap();
+/// let client = ClientBuilder::default()
+///     .api_key("your-api-key")
+///     .retry_policy(policy)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Builder)]
+#[builder(setter(into))]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    #[builder(default = "3")]
+    pub max_attempts: u32,
+    #[builder(default = "Duration::from_millis(500)")]
+    pub base_delay: Duration,
+    /// Upper bound of a computed delay. A `Retry-After` longer than this ends the retries.
+    #[builder(default = "Duration::from_secs(30)")]
+    pub max_delay: Duration,
+    /// Fraction of the delay, between 0 and 1, that may be randomly taken off.
+    #[builder(default = "0.5")]
+    pub jitter: f64,
+    #[builder(default = "vec![429, 500, 502, 503, 504]")]
+    pub retryable_statuses: Vec<u16>,
+    /// Also retry POST, PUT and DELETE requests.
+    #[builder(default = "false")]
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicyBuilder::default().build().unwrap()
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that sends every request once.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Whether requests with this method may be retried at all.
+    pub fn applies_to(&self, method: &Method) -> bool {
+        *method == Method::GET || self.retry_non_idempotent
+    }
+
+    /// The delay before the next attempt after a response, or `None` if it shouldn't be retried.
+    pub fn delay_after_response(&self, response: &Response, attempt: u32) -> Option<Duration> {
+        if !self
+            .retryable_statuses
+            .contains(&response.status().as_u16())
+        {
+            return None;
+        }
+        match retry_after(response) {
+            Some(delay) if delay > self.max_delay => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// The delay before the next attempt after a network error, or `None` if it shouldn't be
+    /// retried.
+    pub fn delay_after_error(&self, error: &reqwest::Error, attempt: u32) -> Option<Duration> {
+        if error.is_connect() || error.is_timeout() {
+            Some(self.backoff(attempt))
+        } else {
+            None
+        }
+    }
+
+    /// Exponential backoff with jitter for the given attempt, counting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .checked_mul(1 << exponent)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        delay.mul_f64(1.0 - rand::thread_rng().gen_range(0.0, 1.0) * jitter)
+    }
+}
+
+/// Reads a `Retry-After` header, given either in seconds or as an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::from_secs(0)),
+    )
+}
@@ -0,0 +1,624 @@
+//! An in-process mock of the updown.io API, for tests that must run without network access.
+//!
+//! [`MockServer`] listens on a local port and emulates `/api/checks`, `/api/checks/:token`,
+//! `/api/checks/:token/downtimes`, `/api/checks/:token/metrics`, `/api/nodes`,
+//! `/api/recipients`, `/api/status_pages` and `/api/webhooks` on top of an in-memory store of
+//! [`Check`], [`Downtime`], [`Node`], [`Recipient`], [`StatusPage`] and [`Webhook`] records.
+//! Pulse checks are given a pulse URL on the server, under `/pulse/:token`. Unknown tokens and
+//! invalid API keys are answered with the same `{"error": "..."}` payloads as the real API.
+//!
+//! Enable it with the `testing` feature:
+//!
+//! ```
+//! use updown::client::ClientBuilder;
+//! use updown::messages::check::Check;
+//! use updown::testing::MockServer;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let server = MockServer::start();
+//! server.add_check(Check {
+//!     token: Some("abcd".to_string()),
+//!     url: Some("https://example.com".to_string()),
+//!     ..Default::default()
+//! });
+//!
+//! let client = ClientBuilder::default()
+//!     .api_key(MockServer::API_KEY)
+//!     .base_url(server.url())
+//!     .build()
+//!     .unwrap();
+//! let check = client.check("abcd", false).await.unwrap();
+//! assert_eq!(check.url.as_deref(), Some("https://example.com"));
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Map, Value};
+use tokio::sync::oneshot;
+use url::form_urlencoded;
+
+use crate::messages::check::{Check, CheckType};
+use crate::messages::downtime::Downtime;
+use crate::messages::metric::Message;
+use crate::messages::node::{Node, Nodes};
+use crate::messages::recipient::{Recipient, RecipientParams};
+use crate::messages::status_page::StatusPage;
+use crate::messages::webhook::Webhook;
+
+/// Downtimes are paginated by the API in pages of this size.
+const DOWNTIMES_PER_PAGE: usize = 100;
+
+/// A request received by the [`MockServer`].
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// The records held by the [`MockServer`].
+#[derive(Default)]
+struct Store {
+    api_key: String,
+    read_only_api_key: String,
+    checks: Vec<Check>,
+    downtimes: HashMap<String, Vec<Downtime>>,
+    metrics: HashMap<String, Message>,
+    nodes: Nodes,
+    recipients: Vec<Recipient>,
+    status_pages: Vec<StatusPage>,
+    webhooks: Vec<Webhook>,
+    requests: Vec<RecordedRequest>,
+    failures: VecDeque<(u16, Option<u64>)>,
+    next_token: u32,
+    next_id: u32,
+}
+
+/// A local HTTP server emulating the updown.io API. It is shut down when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    store: Arc<Mutex<Store>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// The full API key accepted by the server.
+    pub const API_KEY: &'static str = "mock-api-key";
+
+    /// The read-only API key accepted by the server, for GET requests only.
+    pub const READ_ONLY_API_KEY: &'static str = "mock-read-only-api-key";
+
+    /// Starts a server on a free local port, in a background thread with its own runtime, so it
+    /// can be used from both synchronous and asynchronous tests.
+    pub fn start() -> MockServer {
+        let store = Arc::new(Mutex::new(Store {
+            api_key: MockServer::API_KEY.to_string(),
+            read_only_api_key: MockServer::READ_ONLY_API_KEY.to_string(),
+            ..Default::default()
+        }));
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let (addr_tx, addr_rx) = mpsc::channel();
+
+        let server_store = store.clone();
+        let thread = thread::spawn(move || {
+            let mut runtime = tokio::runtime::Runtime::new().expect("Failed to start mock runtime");
+            runtime.block_on(async move {
+                let make_service = make_service_fn(move |_| {
+                    let store = server_store.clone();
+                    async move {
+                        Ok::<_, Infallible>(service_fn(move |request| {
+                            handle(store.clone(), request)
+                        }))
+                    }
+                });
+                let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+                addr_tx.send(server.local_addr()).ok();
+                server
+                    .with_graceful_shutdown(async {
+                        shutdown_rx.await.ok();
+                    })
+                    .await
+                    .ok();
+            });
+        });
+
+        MockServer {
+            addr: addr_rx.recv().expect("Mock server failed to start"),
+            store,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        }
+    }
+
+    /// The base URL to configure the client with.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Adds a check. A token is generated if the check has none.
+    pub fn add_check(&self, mut check: Check) -> Check {
+        let mut store = self.store.lock().unwrap();
+        if check.token.is_none() {
+            check.token = Some(store.generate_token());
+        }
+        store.checks.push(check.clone());
+        check
+    }
+
+    /// Adds a downtime to the check with the given token.
+    pub fn add_downtime(&self, token: &str, downtime: Downtime) {
+        let mut store = self.store.lock().unwrap();
+        store
+            .downtimes
+            .entry(token.to_string())
+            .or_default()
+            .push(downtime);
+    }
+
+    /// Sets the metrics returned for the check with the given token.
+    pub fn set_metrics(&self, token: &str, metrics: Message) {
+        let mut store = self.store.lock().unwrap();
+        store.metrics.insert(token.to_string(), metrics);
+    }
+
+    /// Adds a monitoring location under the given code.
+    pub fn add_node(&self, code: &str, node: Node) {
+        let mut store = self.store.lock().unwrap();
+        store.nodes.insert(code.to_string(), node);
+    }
+
+    /// Adds a recipient. An id is generated if the recipient has none.
+    pub fn add_recipient(&self, mut recipient: Recipient) -> Recipient {
+        let mut store = self.store.lock().unwrap();
+        if recipient.id.is_none() {
+            recipient.id = Some(format!("{}:{}", recipient.kind, store.generate_id()));
+        }
+        store.recipients.push(recipient.clone());
+        recipient
+    }
+
+    /// The recipients currently held by the server.
+    pub fn recipients(&self) -> Vec<Recipient> {
+        self.store.lock().unwrap().recipients.clone()
+    }
+
+    /// Adds a status page. A token is generated if the page has none.
+    pub fn add_status_page(&self, mut status_page: StatusPage) -> StatusPage {
+        let mut store = self.store.lock().unwrap();
+        if status_page.token.is_none() {
+            status_page.token = Some(store.generate_token());
+        }
+        store.status_pages.push(status_page.clone());
+        status_page
+    }
+
+    /// The status pages currently held by the server.
+    pub fn status_pages(&self) -> Vec<StatusPage> {
+        self.store.lock().unwrap().status_pages.clone()
+    }
+
+    /// Adds a webhook. An id is generated if the webhook has none.
+    pub fn add_webhook(&self, mut webhook: Webhook) -> Webhook {
+        let mut store = self.store.lock().unwrap();
+        if webhook.id.is_none() {
+            webhook.id = Some(store.generate_id());
+        }
+        store.webhooks.push(webhook.clone());
+        webhook
+    }
+
+    /// The webhooks currently held by the server.
+    pub fn webhooks(&self) -> Vec<Webhook> {
+        self.store.lock().unwrap().webhooks.clone()
+    }
+
+    /// Answers the next `times` requests with the given HTTP status and an error message, whatever
+    /// they are, e.g. to emulate rate limiting or an outage.
+    pub fn fail_next(&self, status: u16, times: usize) {
+        let mut store = self.store.lock().unwrap();
+        store
+            .failures
+            .extend(std::iter::repeat_n((status, None), times));
+    }
+
+    /// Like [`MockServer::fail_next`], with a `Retry-After` header giving a delay in seconds.
+    pub fn fail_next_with_retry_after(&self, status: u16, times: usize, retry_after: u64) {
+        let mut store = self.store.lock().unwrap();
+        store
+            .failures
+            .extend(std::iter::repeat_n((status, Some(retry_after)), times));
+    }
+
+    /// The checks currently held by the server.
+    pub fn checks(&self) -> Vec<Check> {
+        self.store.lock().unwrap().checks.clone()
+    }
+
+    /// The requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.store.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl Store {
+    /// Tokens are 4 characters long, like the ones handed out by updown.io
+    fn generate_token(&mut self) -> String {
+        self.next_token += 1;
+        format!("mk{:02}", self.next_token % 100)
+    }
+
+    /// Ids are 24 hexadecimal characters long, like the ones handed out by updown.io
+    fn generate_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("{:024x}", self.next_id)
+    }
+
+    fn check_index(&self, token: &str) -> Option<usize> {
+        self.checks
+            .iter()
+            .position(|c| c.token.as_deref() == Some(token))
+    }
+}
+
+async fn handle(
+    store: Arc<Mutex<Store>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(|q| form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+        .unwrap_or_default();
+    let headers: HashMap<String, String> = request
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+        .collect();
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .map(|b| String::from_utf8_lossy(&b).to_string())
+        .unwrap_or_default();
+
+    let recorded = RecordedRequest {
+        method: method.to_string(),
+        path: path.clone(),
+        query,
+        headers,
+        body,
+    };
+    let mut store = store.lock().unwrap();
+    store.requests.push(recorded.clone());
+
+    if let Some((status, retry_after)) = store.failures.pop_front() {
+        let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = error(status, status.canonical_reason().unwrap_or("Error"));
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert("Retry-After", seconds.to_string().parse().unwrap());
+        }
+        return Ok(response);
+    }
+
+    let body = parse_body(&recorded.body);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    // Pulse URLs identify the check, they aren't authenticated with an API key
+    if let ["pulse", token] = segments.as_slice() {
+        let is_pulse = |c: &Check| {
+            c.token.as_deref() == Some(*token) && c.check_type == Some(CheckType::Pulse)
+        };
+        return Ok(if store.checks.iter().any(is_pulse) {
+            ok(json!("OK"))
+        } else {
+            error(StatusCode::NOT_FOUND, "Pulse check not found")
+        });
+    }
+
+    match api_key(&recorded, &body) {
+        Some(key) if key == store.api_key => {}
+        Some(key) if key == store.read_only_api_key && method == Method::GET => {}
+        Some(key) if key == store.read_only_api_key => {
+            return Ok(error(StatusCode::UNAUTHORIZED, "Read-only API key"));
+        }
+        _ => return Ok(error(StatusCode::UNAUTHORIZED, "Invalid API key")),
+    }
+
+    let response = match (&method, segments.as_slice()) {
+        (&Method::GET, ["api", "checks"]) => ok(json!(store.checks)),
+        (&Method::POST, ["api", "checks"]) => {
+            let host = recorded.headers.get("host").cloned().unwrap_or_default();
+            create_check(&mut store, &host, body)
+        }
+        (&Method::GET, ["api", "checks", token]) => match store.check_index(token) {
+            Some(i) => {
+                let mut check = json!(store.checks[i]);
+                if recorded.query.get("metrics").map(String::as_str) == Some("true") {
+                    let metrics = store.metrics.get(*token).cloned();
+                    check["metrics"] = json!(metrics);
+                }
+                ok(check)
+            }
+            None => not_found(),
+        },
+        (&Method::PUT, ["api", "checks", token]) => match store.check_index(token) {
+            Some(i) => update_check(&mut store, i, body),
+            None => not_found(),
+        },
+        (&Method::DELETE, ["api", "checks", token]) => match store.check_index(token) {
+            Some(i) => {
+                store.checks.remove(i);
+                store.downtimes.remove(*token);
+                ok(json!({ "deleted": true }))
+            }
+            None => not_found(),
+        },
+        (&Method::GET, ["api", "checks", token, "downtimes"]) => match store.check_index(token) {
+            Some(_) => {
+                let page: usize = recorded
+                    .query
+                    .get("page")
+                    .and_then(|p| p.parse().ok())
+                    .unwrap_or(1);
+                let downtimes: Vec<&Downtime> = store
+                    .downtimes
+                    .get(*token)
+                    .map(|d| {
+                        d.iter()
+                            .skip((page.max(1) - 1) * DOWNTIMES_PER_PAGE)
+                            .take(DOWNTIMES_PER_PAGE)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                ok(json!(downtimes))
+            }
+            None => not_found(),
+        },
+        (&Method::GET, ["api", "checks", token, "metrics"]) => match store.check_index(token) {
+            Some(_) => ok(json!(store.metrics.get(*token))),
+            None => not_found(),
+        },
+        (&Method::GET, ["api", "nodes"]) => ok(json!(store.nodes)),
+        (&Method::GET, ["api", "nodes", "ipv4"]) => {
+            let ips: Vec<&String> = store.nodes.values().filter_map(|n| n.ip.as_ref()).collect();
+            ok(json!(ips))
+        }
+        (&Method::GET, ["api", "nodes", "ipv6"]) => {
+            let ips: Vec<&String> = store
+                .nodes
+                .values()
+                .filter_map(|n| n.ip6.as_ref())
+                .collect();
+            ok(json!(ips))
+        }
+        (&Method::GET, ["api", "recipients"]) => ok(json!(store.recipients)),
+        (&Method::POST, ["api", "recipients"]) => {
+            match serde_json::from_value::<RecipientParams>(Value::Object(body)) {
+                Ok(params) => {
+                    let recipient = Recipient {
+                        id: Some(format!("{}:{}", params.kind, store.generate_id())),
+                        kind: params.kind,
+                        name: params.name,
+                        value: Some(params.value),
+                    };
+                    store.recipients.push(recipient.clone());
+                    created(json!(recipient))
+                }
+                Err(e) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+            }
+        }
+        (&Method::DELETE, ["api", "recipients", id]) => {
+            match store
+                .recipients
+                .iter()
+                .position(|r| r.id.as_deref() == Some(*id))
+            {
+                Some(i) => {
+                    store.recipients.remove(i);
+                    ok(json!({ "deleted": true }))
+                }
+                None => error(StatusCode::NOT_FOUND, "Recipient not found"),
+            }
+        }
+        (&Method::GET, ["api", "status_pages"]) => ok(json!(store.status_pages)),
+        (&Method::POST, ["api", "status_pages"]) => {
+            let mut status_page = Map::new();
+            let token = store.generate_token();
+            status_page.insert(
+                "url".to_string(),
+                json!(format!("https://status.example.com/{}", token)),
+            );
+            status_page.insert("token".to_string(), json!(token));
+            status_page.insert("visibility".to_string(), json!("public"));
+            merge(&mut status_page, body);
+            match serde_json::from_value::<StatusPage>(Value::Object(status_page)) {
+                Ok(status_page) => {
+                    store.status_pages.push(status_page.clone());
+                    created(json!(status_page))
+                }
+                Err(e) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+            }
+        }
+        (&Method::PUT, ["api", "status_pages", token]) => {
+            match store
+                .status_pages
+                .iter()
+                .position(|p| p.token.as_deref() == Some(*token))
+            {
+                Some(i) => {
+                    let mut status_page = match json!(store.status_pages[i]) {
+                        Value::Object(status_page) => status_page,
+                        _ => unreachable!(),
+                    };
+                    merge(&mut status_page, body);
+                    match serde_json::from_value::<StatusPage>(Value::Object(status_page)) {
+                        Ok(status_page) => {
+                            store.status_pages[i] = status_page.clone();
+                            ok(json!(status_page))
+                        }
+                        Err(e) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+                    }
+                }
+                None => error(StatusCode::NOT_FOUND, "Status page not found"),
+            }
+        }
+        (&Method::DELETE, ["api", "status_pages", token]) => {
+            match store
+                .status_pages
+                .iter()
+                .position(|p| p.token.as_deref() == Some(*token))
+            {
+                Some(i) => {
+                    store.status_pages.remove(i);
+                    ok(json!({ "deleted": true }))
+                }
+                None => error(StatusCode::NOT_FOUND, "Status page not found"),
+            }
+        }
+        (&Method::GET, ["api", "webhooks"]) => ok(json!(store.webhooks)),
+        (&Method::POST, ["api", "webhooks"]) => match body.get("url").and_then(Value::as_str) {
+            Some(url) => {
+                let webhook = Webhook {
+                    id: Some(store.generate_id()),
+                    url: Some(url.to_string()),
+                };
+                store.webhooks.push(webhook.clone());
+                created(json!(webhook))
+            }
+            None => error(StatusCode::BAD_REQUEST, "URL is required"),
+        },
+        (&Method::DELETE, ["api", "webhooks", id]) => {
+            match store
+                .webhooks
+                .iter()
+                .position(|w| w.id.as_deref() == Some(*id))
+            {
+                Some(i) => {
+                    store.webhooks.remove(i);
+                    ok(json!({ "deleted": true }))
+                }
+                None => error(StatusCode::NOT_FOUND, "Webhook not found"),
+            }
+        }
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    };
+    Ok(response)
+}
+
+fn create_check(store: &mut Store, host: &str, body: Map<String, Value>) -> Response<Body> {
+    let token = store.generate_token();
+    let mut check = Map::new();
+    if body.get("type").and_then(Value::as_str) == Some("pulse") {
+        check.insert(
+            "pulse_url".to_string(),
+            json!(format!("http://{}/pulse/{}", host, token)),
+        );
+    } else if !body.contains_key("url") {
+        return error(StatusCode::BAD_REQUEST, "URL is required");
+    } else {
+        check.insert("type".to_string(), json!("http"));
+    }
+    check.insert("token".to_string(), json!(token));
+    check.insert("enabled".to_string(), json!(true));
+    check.insert("published".to_string(), json!(false));
+    check.insert("period".to_string(), json!(60));
+    check.insert("apdex_t".to_string(), json!(0.5));
+    merge(&mut check, body);
+    match serde_json::from_value::<Check>(Value::Object(check)) {
+        Ok(check) => {
+            store.checks.push(check.clone());
+            created(json!(check))
+        }
+        Err(e) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+    }
+}
+
+fn update_check(store: &mut Store, index: usize, body: Map<String, Value>) -> Response<Body> {
+    let mut check = match json!(store.checks[index]) {
+        Value::Object(check) => check,
+        _ => unreachable!(),
+    };
+    merge(&mut check, body);
+    match serde_json::from_value::<Check>(Value::Object(check)) {
+        Ok(check) => {
+            store.checks[index] = check.clone();
+            ok(json!(check))
+        }
+        Err(e) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+    }
+}
+
+/// Copies the request parameters onto a record; authentication and the token are not record
+/// fields.
+fn merge(check: &mut Map<String, Value>, body: Map<String, Value>) {
+    for (key, value) in body {
+        if key != "api-key" && key != "token" {
+            check.insert(key, value);
+        }
+    }
+}
+
+/// Request parameters are accepted as a JSON body, as sent by the client.
+fn parse_body(body: &str) -> Map<String, Value> {
+    match serde_json::from_str(body) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn api_key<'a>(request: &'a RecordedRequest, body: &'a Map<String, Value>) -> Option<&'a str> {
+    request
+        .headers
+        .get("x-api-key")
+        .or_else(|| request.query.get("api-key"))
+        .map(String::as_str)
+        .or_else(|| body.get("api-key").and_then(Value::as_str))
+}
+
+fn ok(body: Value) -> Response<Body> {
+    respond(StatusCode::OK, body)
+}
+
+fn created(body: Value) -> Response<Body> {
+    respond(StatusCode::CREATED, body)
+}
+
+fn not_found() -> Response<Body> {
+    error(StatusCode::NOT_FOUND, "Check not found")
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    respond(status, json!({ "error": message }))
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// Time is a timestamp given on the command line, resolved to UTC. It can be:
+///
+/// - `now`, or a time relative to now: a sign followed by amounts of seconds, minutes, hours,
+///   days or weeks, e.g. `-7d`, `+2h` or `-1h30m`
+/// - an RFC 3339 date and time, e.g. `2020-10-01T12:00:00+02:00`
+/// - a date and time, e.g. `2020-10-01 12:00`, or a date, e.g. `2020-10-01` for midnight, in UTC
+///   unless followed by a time zone name, e.g. `2020-10-01 12:00 Europe/Paris`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Time(pub DateTime<Utc>);
+
+const FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+impl Time {
+    /// Resolves a time relative to `now`; see [`Time`] for the accepted inputs.
+    pub fn parse_at(s: &str, now: DateTime<Utc>) -> Result<Time, String> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("now") {
+            return Ok(Time(now));
+        }
+        if s.starts_with('+') || s.starts_with('-') {
+            return relative(s).and_then(|offset| {
+                now.checked_add_signed(offset)
+                    .map(Time)
+                    .ok_or_else(|| format!("'{}' is too far from now", s))
+            });
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Time(time.with_timezone(&Utc)));
+        }
+        if let Some(time) = local(s, &Utc) {
+            return Ok(Time(time));
+        }
+        if let Some((local_time, zone)) = s.rsplit_once(' ') {
+            if let Ok(zone) = zone.parse::<Tz>() {
+                return local(local_time.trim(), &zone).map(Time).ok_or_else(|| {
+                    format!(
+                        "'{}' is not a valid date and time in {}",
+                        local_time.trim(),
+                        zone
+                    )
+                });
+            }
+        }
+        Err(
+            "must be now, a relative time such as -7d or +2h, or a date and time such as \
+             2020-10-01 12:00 Europe/Paris"
+                .to_string(),
+        )
+    }
+}
+
+impl FromStr for Time {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Time::parse_at(s, Utc::now())
+    }
+}
+
+/// Parses a date, or a date and time, in the given time zone. Times skipped or repeated by a
+/// daylight saving change are refused.
+fn local<Z: TimeZone>(s: &str, zone: &Z) -> Option<DateTime<Utc>> {
+    let naive = FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    zone.from_local_datetime(&naive)
+        .single()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Parses an offset such as `-7d`, `+2h` or `-1h30m`.
+fn relative(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("'{}' is not a relative time such as -7d, +2h or -1h30m", s);
+    let (sign, amounts) = s.split_at(1);
+    if amounts.is_empty() {
+        return Err(invalid());
+    }
+    let mut offset = Duration::zero();
+    let mut digits = String::new();
+    for c in amounts.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let amount: i64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        let unit = match c {
+            's' => Duration::try_seconds(amount),
+            'm' => Duration::try_minutes(amount),
+            'h' => Duration::try_hours(amount),
+            'd' => Duration::try_days(amount),
+            'w' => Duration::try_weeks(amount),
+            _ => None,
+        };
+        offset = unit
+            .and_then(|unit| offset.checked_add(&unit))
+            .ok_or_else(invalid)?;
+    }
+    if !digits.is_empty() {
+        return Err(invalid());
+    }
+    Ok(if sign == "-" { -offset } else { offset })
+}
@@ -0,0 +1,1164 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde_json::Value;
+use tempfile::TempDir;
+use updown::messages::check::{Check, MuteUntil, Period};
+use updown::messages::downtime::Downtime;
+use updown::messages::metric::Message;
+use updown::messages::node::Node;
+use updown::testing::MockServer;
+
+/// Runs the `updown` binary against the mock server, with its configuration kept in a temporary
+/// directory.
+struct Cli {
+    server: MockServer,
+    config_dir: TempDir,
+}
+
+impl Cli {
+    fn new() -> Cli {
+        let cli = Cli {
+            server: MockServer::start(),
+            config_dir: TempDir::new().unwrap(),
+        };
+        let output = cli.run(&[
+            "config",
+            MockServer::API_KEY,
+            MockServer::READ_ONLY_API_KEY,
+            "tests",
+        ]);
+        assert!(output.status.success());
+        cli
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_updown"));
+        command
+            .args(args)
+            .env("XDG_CONFIG_HOME", self.config_dir.path())
+            .env("HOME", self.config_dir.path())
+            .env("UPDOWN_BASE_URL", self.server.url());
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    /// Runs a command that is expected to fail, returning its exit code and error output.
+    fn fail(&self, args: &[&str]) -> (i32, String) {
+        let output = self.run(args);
+        assert!(!output.status.success(), "updown {:?} succeeded", args);
+        (
+            output.status.code().unwrap(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        )
+    }
+
+    fn json(&self, args: &[&str]) -> Value {
+        let output = self.run(args);
+        assert!(
+            output.status.success(),
+            "updown {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+}
+
+fn example_check(token: &str) -> Check {
+    Check {
+        token: Some(token.to_string()),
+        url: Some(format!("https://{}.example.com", token)),
+        period: Some(Period::Min1),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn all_prints_every_check() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_check(example_check("bbbb"));
+
+    let checks = cli.json(&["all"]);
+
+    assert_eq!(checks.as_array().unwrap().len(), 2);
+    assert_eq!(checks[1]["token"], "bbbb");
+}
+
+#[test]
+fn check_prints_the_check() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let check = cli.json(&["check", "aaaa"]);
+
+    assert_eq!(check["url"], "https://aaaa.example.com");
+}
+
+#[test]
+fn read_only_fields_are_printed() {
+    let cli = Cli::new();
+    cli.server.add_check(Check {
+        uptime: Some(99.5),
+        down: Some(false),
+        ..example_check("aaaa")
+    });
+    cli.server.set_metrics(
+        "aaaa",
+        Message {
+            apdex: Some(0.75),
+            requests: None,
+            timings: None,
+        },
+    );
+
+    let checks = cli.json(&["all"]);
+    assert_eq!(checks[0]["uptime"], 99.5);
+    assert_eq!(checks[0]["down"], false);
+    let check = cli.json(&["check", "aaaa", "--metrics"]);
+    assert_eq!(check["metrics"]["apdex"], 0.75);
+    let yaml = cli.run(&["all", "--output", "yaml"]);
+    assert!(String::from_utf8_lossy(&yaml.stdout).contains("uptime: 99.5"));
+}
+
+#[test]
+fn checks_are_found_by_alias_or_url() {
+    let cli = Cli::new();
+    cli.server.add_check(Check {
+        alias: Some("shop".to_string()),
+        ..example_check("aaaa")
+    });
+    cli.server.add_check(example_check("bbbb"));
+
+    assert_eq!(cli.json(&["shop"])["token"], "aaaa");
+    assert_eq!(
+        cli.json(&["check", "https://bbbb.example.com/"])["token"],
+        "bbbb"
+    );
+    cli.json(&["downtimes", "https://b*"]);
+    assert_eq!(
+        cli.server.requests().pop().unwrap().path,
+        "/api/checks/bbbb/downtimes"
+    );
+
+    cli.json(&["delete", "shop"]);
+    assert_eq!(cli.server.checks().len(), 1);
+}
+
+#[test]
+fn tokens_are_used_without_loading_every_check() {
+    let cli = Cli::new();
+    cli.server.add_check(Check {
+        alias: Some("shop".to_string()),
+        ..example_check("aaaa")
+    });
+    let paths = || -> Vec<String> { cli.server.requests().into_iter().map(|r| r.path).collect() };
+
+    cli.json(&["check", "aaaa"]);
+    cli.json(&["update", "aaaa", "--period", "60"]);
+    assert_eq!(paths(), ["/api/checks/aaaa", "/api/checks/aaaa"]);
+
+    cli.json(&["downtimes", "shop"]);
+    assert_eq!(
+        paths()[2..],
+        [
+            "/api/checks/shop/downtimes",
+            "/api/checks",
+            "/api/checks/aaaa/downtimes"
+        ]
+    );
+}
+
+#[test]
+fn unknown_aliases_and_urls_are_usage_errors() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let (code, stderr) = cli.fail(&["delete", "https://nope.example.com"]);
+
+    assert_eq!(code, exitcode::USAGE);
+    assert!(
+        stderr.contains("no check has the token, alias or URL https://nope.example.com"),
+        "{}",
+        stderr
+    );
+    assert_eq!(cli.server.requests().len(), 1);
+    assert_eq!(cli.server.checks().len(), 1);
+}
+
+#[test]
+fn ambiguous_check_reference_lists_the_candidates() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_check(example_check("bbbb"));
+
+    let (code, stderr) = cli.fail(&["update", "https://*.example.com", "--period", "60"]);
+
+    assert_eq!(code, exitcode::USAGE);
+    assert!(
+        stderr.contains("aaaa (https://aaaa.example.com), bbbb (https://bbbb.example.com)"),
+        "{}",
+        stderr
+    );
+    let (code, _) = cli.fail(&["metrics", "https://*.example.org"]);
+    assert_eq!(code, exitcode::USAGE);
+}
+
+#[test]
+fn add_creates_a_check() {
+    let cli = Cli::new();
+
+    let check = cli.json(&[
+        "add",
+        "https://new.example.com",
+        "--alias",
+        "new",
+        "--apdex-t",
+        "1.0",
+    ]);
+
+    assert_eq!(check["url"], "https://new.example.com");
+    let checks = cli.server.checks();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].alias.as_deref(), Some("new"));
+    assert_eq!(checks[0].apdex_t, Some(1.0));
+}
+
+fn add_nodes(cli: &Cli) {
+    for code in &["lan", "mia", "syd"] {
+        cli.server.add_node(code, Node::default());
+    }
+}
+
+#[test]
+fn add_sends_custom_headers_and_disabled_locations() {
+    let cli = Cli::new();
+    add_nodes(&cli);
+
+    cli.json(&[
+        "add",
+        "https://example.com",
+        "--header",
+        "Authorization: Bearer abc",
+        "--custom-header",
+        "X-Env:staging",
+        "--disabled-location",
+        "lan",
+        "--disabled-location",
+        "syd",
+    ]);
+
+    let check = &cli.server.checks()[0];
+    let headers = check.custom_headers.as_ref().unwrap();
+    assert_eq!(headers["Authorization"], "Bearer abc");
+    assert_eq!(headers["X-Env"], "staging");
+    assert_eq!(
+        check.disabled_locations,
+        Some(vec!["lan".to_string(), "syd".to_string()])
+    );
+}
+
+#[test]
+fn update_clears_custom_headers_and_disabled_locations() {
+    let cli = Cli::new();
+    add_nodes(&cli);
+    cli.json(&[
+        "add",
+        "https://example.com",
+        "--header",
+        "X-Env: staging",
+        "--disabled-location",
+        "mia",
+    ]);
+    let token = cli.server.checks()[0].token.clone().unwrap();
+
+    cli.json(&[
+        "update",
+        &token,
+        "--clear-headers",
+        "--clear-disabled-locations",
+    ]);
+
+    let check = &cli.server.checks()[0];
+    assert!(check.custom_headers.as_ref().unwrap().is_empty());
+    assert!(check.disabled_locations.as_ref().unwrap().is_empty());
+}
+
+#[test]
+fn clearing_and_setting_the_same_field_is_a_usage_error() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    for args in &[
+        ["--disabled-location", "mia", "--clear-disabled-locations"],
+        ["--header", "X-Env: staging", "--clear-headers"],
+    ] {
+        let mut command = vec!["update", "aaaa"];
+        command.extend(args);
+        let (code, stderr) = cli.fail(&command);
+        assert_eq!(code, exitcode::USAGE);
+        assert!(stderr.contains("cannot be used with"), "{}", stderr);
+    }
+}
+
+#[test]
+fn malformed_headers_and_locations_are_usage_errors() {
+    let cli = Cli::new();
+    add_nodes(&cli);
+
+    let (code, stderr) = cli.fail(&[
+        "add",
+        "https://example.com",
+        "--header",
+        "no colon",
+        "--header",
+        "Bad Name: x",
+        "--disabled-location",
+        "LAN",
+    ]);
+    assert_eq!(code, exitcode::USAGE);
+    assert!(
+        stderr.contains("must be given as 'Name: value'"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("'Bad Name' is not a valid header name"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("3 lowercase letters"), "{}", stderr);
+
+    let (code, stderr) = cli.fail(&["add", "https://example.com", "--disabled-location", "xyz"]);
+    assert_eq!(code, exitcode::USAGE);
+    assert!(stderr.contains("unknown location xyz"), "{}", stderr);
+    assert!(cli.server.checks().is_empty());
+}
+
+#[test]
+fn apply_converges_on_a_manifest() {
+    let cli = Cli::new();
+    add_nodes(&cli);
+    cli.server.add_check(Check {
+        alias: Some("shop".to_string()),
+        ..example_check("aaaa")
+    });
+    cli.server.add_check(example_check("bbbb"));
+    let manifest = cli.config_dir.path().join("checks.yml");
+    std::fs::write(
+        &manifest,
+        "checks:
+  - url: https://aaaa.example.com
+    alias: shop
+    period: 300
+    disabled_locations: [lan]
+  - url: https://new.example.com
+    headers:
+      X-Token: abc
+",
+    )
+    .unwrap();
+    let path = manifest.to_str().unwrap();
+
+    let changes = cli.json(&["apply", path]);
+
+    assert_eq!(changes.as_array().unwrap().len(), 2);
+    assert_eq!(changes[0]["action"], "update");
+    assert_eq!(changes[1]["action"], "create");
+    let checks = cli.server.checks();
+    assert_eq!(checks.len(), 3);
+    assert_eq!(checks[0].period, Some(Period::Min5));
+    assert_eq!(checks[2].custom_headers.as_ref().unwrap()["X-Token"], "abc");
+
+    let changes = cli.json(&["apply", path, "--prune"]);
+
+    assert_eq!(
+        changes,
+        serde_json::json!([{"action": "delete", "check": "https://bbbb.example.com", "token": "bbbb"}])
+    );
+    assert_eq!(cli.server.checks().len(), 2);
+    assert_eq!(cli.json(&["apply", path, "--prune"]), serde_json::json!([]));
+}
+
+#[test]
+fn plan_prints_the_drift_and_fails() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_check(example_check("bbbb"));
+    let manifest = cli.config_dir.path().join("checks.json");
+    std::fs::write(
+        &manifest,
+        r#"{"checks": [{"url": "https://aaaa.example.com", "period": 300, "alias": "shop"}]}"#,
+    )
+    .unwrap();
+    let path = manifest.to_str().unwrap();
+
+    let output = cli.run(&["plan", path, "--prune"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "@@ update shop (aaaa) @@
++alias: \"shop\"
+-period: 60
++period: 300
+@@ delete https://bbbb.example.com (bbbb) @@
+"
+    );
+    let patch = cli.run(&["plan", path, "--json-patch"]);
+    assert_eq!(
+        serde_json::from_slice::<Value>(&patch.stdout).unwrap(),
+        serde_json::json!([
+            {"op": "add", "path": "/shop/alias", "value": "shop"},
+            {"op": "replace", "path": "/shop/period", "value": 300},
+        ])
+    );
+    assert_eq!(cli.server.checks()[0].period, Some(Period::Min1));
+
+    cli.json(&["apply", path]);
+    let output = cli.run(&["plan", path]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn dry_run_changes_nothing() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let output = cli.run(&["update", "aaaa", "--period", "300", "--dry-run"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "@@ update https://aaaa.example.com (aaaa) @@\n-period: 60\n+period: 300\n"
+    );
+    let output = cli.run(&["delete", "aaaa", "--dry-run", "--json-patch"]);
+    assert_eq!(
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()[0]["op"],
+        "remove"
+    );
+    let output = cli.run(&["add", "https://new.example.com", "--dry-run"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "@@ create https://new.example.com @@\n+url: \"https://new.example.com\"\n"
+    );
+    let changes = cli.json(&[
+        "update",
+        "aaaa",
+        "--period",
+        "300",
+        "--dry-run",
+        "--output",
+        "json",
+    ]);
+    assert_eq!(changes[0]["diff"][0]["new"], 300);
+    let output = cli.run(&["delete", "aaaa", "--dry-run", "--output", "yaml"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("action: delete"));
+
+    let checks = cli.server.checks();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].period, Some(Period::Min1));
+}
+
+#[test]
+fn export_refuses_checks_a_manifest_cannot_tell_apart() {
+    let cli = Cli::new();
+    cli.json(&["add", "--pulse"]);
+    let pulse = cli.server.checks()[0].token.clone().unwrap();
+
+    let (code, stderr) = cli.fail(&["export"]);
+    assert_eq!(code, exitcode::DATAERR);
+    assert!(
+        stderr.contains(&format!("{} is a pulse check without an alias", pulse)),
+        "{}",
+        stderr
+    );
+
+    cli.run(&["update", &pulse, "--alias", "backup"]);
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_check(Check {
+        url: Some("https://aaaa.example.com/".to_string()),
+        ..example_check("bbbb")
+    });
+    let (code, stderr) = cli.fail(&["export"]);
+    assert_eq!(code, exitcode::DATAERR);
+    assert!(
+        stderr.contains("aaaa, bbbb all have https://aaaa.example.com"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn export_round_trips_through_apply() {
+    let cli = Cli::new();
+    cli.server.add_check(Check {
+        alias: Some("shop".to_string()),
+        disabled_locations: Some(vec!["syd".to_string(), "lan".to_string()]),
+        custom_headers: Some(
+            vec![("X-Token".to_string(), "abc".to_string())]
+                .into_iter()
+                .collect(),
+        ),
+        enabled: Some(true),
+        uptime: Some(99.5),
+        ..example_check("bbbb")
+    });
+    cli.server.add_check(example_check("aaaa"));
+    cli.json(&["add", "--pulse", "--alias", "backup"]);
+
+    for name in &["checks.yml", "checks.toml", "checks.json"] {
+        let manifest = cli.config_dir.path().join(name);
+        let path = manifest.to_str().unwrap();
+        assert!(cli.run(&["export", path]).status.success());
+
+        let output = cli.run(&["plan", path, "--prune"]);
+        assert!(
+            output.status.success(),
+            "{}: {}",
+            std::fs::read_to_string(&manifest).unwrap(),
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+
+    let output = cli.run(&["export"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "---
+checks:
+  - type: pulse
+    alias: backup
+    period: 60
+    apdex_t: 0.5
+    enabled: true
+    published: false
+  - url: \"https://aaaa.example.com\"
+    period: 60
+  - url: \"https://bbbb.example.com\"
+    alias: shop
+    period: 60
+    disabled_locations:
+      - lan
+      - syd
+    enabled: true
+    headers:
+      X-Token: abc
+"
+    );
+}
+
+#[test]
+fn bulk_changes_the_selected_checks() {
+    let cli = Cli::new();
+    for (token, alias) in &[("aaaa", "shop-eu"), ("bbbb", "shop-us"), ("cccc", "blog")] {
+        cli.server.add_check(Check {
+            alias: Some(alias.to_string()),
+            enabled: Some(true),
+            ..example_check(token)
+        });
+    }
+
+    let outcomes = cli.json(&["bulk", "disable", "--alias-glob", "shop-*", "--yes"]);
+
+    assert_eq!(outcomes.as_array().unwrap().len(), 2);
+    assert_eq!(outcomes[1]["check"], "shop-us");
+    let enabled: Vec<_> = cli.server.checks().iter().map(|c| c.enabled).collect();
+    assert_eq!(enabled, vec![Some(false), Some(false), Some(true)]);
+
+    cli.json(&[
+        "bulk",
+        "set-period",
+        "300",
+        "--disabled",
+        "--url-regex",
+        "aaaa",
+        "-y",
+    ]);
+    cli.json(&["bulk", "mute", "--until", "+2h", "--all", "--yes"]);
+    cli.json(&["bulk", "unmute", "--period", "300", "--yes"]);
+    let checks = cli.server.checks();
+    assert_eq!(checks[0].period, Some(Period::Min5));
+    assert_eq!(checks[0].mute_until, Some(MuteUntil::Unmuted));
+    assert!(matches!(checks[1].mute_until, Some(MuteUntil::Time(_))));
+
+    let (code, _) = cli.fail(&["bulk", "delete", "--yes"]);
+    assert_eq!(code, exitcode::USAGE);
+    let (code, stderr) = cli.fail(&["bulk", "set-period", "7", "--all", "--yes"]);
+    assert_eq!(code, exitcode::DATAERR);
+    assert!(
+        stderr.contains("period: must be one of 15, 30, 60, 120, 300, 600, 1800 or 3600 (7 given)"),
+        "{}",
+        stderr
+    );
+    assert_eq!(cli.server.checks().len(), 3);
+}
+
+#[test]
+fn bulk_asks_for_confirmation() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_check(example_check("bbbb"));
+    let answer = |answer: &str| {
+        let mut child = cli
+            .command(&["bulk", "delete", "--url-regex", "^https://a"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(answer.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = answer("n\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("delete this check? [y/N]"));
+    assert_eq!(cli.server.checks().len(), 2);
+
+    let output = answer("y\n");
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("[1/1] https://aaaa.example.com: done")
+    );
+    assert_eq!(cli.server.checks().len(), 1);
+}
+
+#[test]
+fn profiles_select_the_account() {
+    let cli = Cli::new();
+    let staging = MockServer::start();
+    staging.add_check(example_check("ssss"));
+    cli.server.add_check(example_check("pppp"));
+    let output = cli.run(&[
+        "config",
+        "add-profile",
+        "staging",
+        MockServer::API_KEY,
+        MockServer::READ_ONLY_API_KEY,
+        "tests",
+        "--base-url",
+        &staging.url(),
+    ]);
+    assert!(output.status.success());
+    let token = |args: &[&str], profile_env: Option<&str>| {
+        let mut command = cli.command(args);
+        command.env_remove("UPDOWN_BASE_URL");
+        if let Some(profile) = profile_env {
+            command.env("UPDOWN_PROFILE", profile);
+        }
+        let output = command.output().unwrap();
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()[0]["token"].clone()
+    };
+
+    // The default profile has no base URL, so it is only used through UPDOWN_BASE_URL here
+    assert_eq!(cli.json(&["all"])[0]["token"], "pppp");
+    assert_eq!(token(&["all", "--profile", "staging"], None), "ssss");
+    assert_eq!(token(&["all"], Some("staging")), "ssss");
+
+    cli.run(&["config", "use", "staging"]);
+    assert_eq!(token(&["all"], None), "ssss");
+    let profiles = cli.json(&["config", "list"]);
+    assert_eq!(profiles[0]["name"], "default");
+    assert_eq!(profiles[1]["current"], true);
+    assert!(profiles[1].get("api_key").is_none());
+
+    let (code, stderr) = cli.fail(&["all", "--profile", "production"]);
+    assert_eq!(code, exitcode::USAGE);
+    assert!(
+        stderr.contains("unknown profile production, the profiles are default, staging"),
+        "{}",
+        stderr
+    );
+
+    cli.run(&["config", "remove", "staging"]);
+    assert_eq!(cli.json(&["config", "list"])[0]["current"], true);
+}
+
+#[test]
+fn copy_creates_the_check_in_another_account() {
+    let cli = Cli::new();
+    let production = MockServer::start();
+    cli.server.add_check(Check {
+        alias: Some("shop".to_string()),
+        string_match: Some("Add to cart".to_string()),
+        recipients: Some(vec!["email:1".to_string()]),
+        ..example_check("aaaa")
+    });
+    for (name, server) in &[("staging", &cli.server), ("production", &production)] {
+        let output = cli.run(&[
+            "config",
+            "add-profile",
+            name,
+            MockServer::API_KEY,
+            MockServer::READ_ONLY_API_KEY,
+            "tests",
+            "--base-url",
+            &server.url(),
+        ]);
+        assert!(output.status.success());
+    }
+    let copy = || {
+        cli.command(&["copy", "shop", "--profile", "staging", "--to", "production"])
+            .env_remove("UPDOWN_BASE_URL")
+            .output()
+            .unwrap()
+    };
+
+    // A token equal to the alias is no duplicate
+    production.add_check(example_check("shop"));
+    assert!(copy().status.success());
+    let copied = production.checks();
+    assert_eq!(copied.len(), 2);
+    assert_eq!(copied[1].alias.as_deref(), Some("shop"));
+    assert_eq!(copied[1].string_match.as_deref(), Some("Add to cart"));
+    assert_eq!(copied[1].recipients, None);
+
+    let output = copy();
+    assert_eq!(output.status.code(), Some(exitcode::USAGE));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("the production profile already has a check shop"));
+    assert_eq!(production.checks().len(), 2);
+}
+
+#[test]
+fn invalid_manifest_is_reported_as_bad_input() {
+    let cli = Cli::new();
+    let manifest = cli.config_dir.path().join("checks.toml");
+    std::fs::write(&manifest, "[[checks]]\nalias = \"shop\"\n").unwrap();
+
+    let (code, stderr) = cli.fail(&["apply", manifest.to_str().unwrap()]);
+
+    assert_eq!(code, exitcode::DATAERR);
+    assert!(stderr.contains("a check has no url"), "{}", stderr);
+    let (code, _) = cli.fail(&["apply", "missing.yml"]);
+    assert_eq!(code, exitcode::NOINPUT);
+}
+
+#[test]
+fn pulse_pings_a_pulse_check_by_token() {
+    let cli = Cli::new();
+
+    let check = cli.json(&["add", "--pulse", "--alias", "backup"]);
+    assert_eq!(check["type"], "pulse");
+    let token = check["token"].as_str().unwrap();
+    assert_eq!(
+        check["pulse_url"],
+        format!("{}/pulse/{}", cli.server.url(), token)
+    );
+    assert_eq!(cli.json(&["check", token])["pulse_url"], check["pulse_url"]);
+
+    let output = cli.run(&["pulse", token, "--exit-code", "-1", "--duration", "3"]);
+    assert!(output.status.success());
+
+    let request = cli.server.requests().pop().unwrap();
+    assert_eq!(request.path, format!("/pulse/{}", token));
+    assert_eq!(request.body, r#"{"exit_code":-1,"duration":3.0}"#);
+}
+
+#[test]
+fn pulse_url_is_pinged_without_configuration() {
+    let cli = Cli::new();
+    let check = cli.json(&["add", "--pulse"]);
+    let pulse_url = check["pulse_url"].as_str().unwrap();
+    let unconfigured = TempDir::new().unwrap();
+
+    let pulse = |token: &str| {
+        cli.command(&["pulse", token])
+            .env("XDG_CONFIG_HOME", unconfigured.path())
+            .env("HOME", unconfigured.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = pulse(pulse_url);
+    assert!(output.status.success(), "{:?}", output);
+    // A token that merely starts like a URL still needs a key
+    let output = pulse("httpx");
+    assert_eq!(output.status.code(), Some(exitcode::NOPERM));
+}
+
+#[test]
+fn pulse_refuses_http_checks() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let (code, stderr) = cli.fail(&["pulse", "aaaa"]);
+
+    assert_eq!(code, exitcode::USAGE);
+    assert!(stderr.contains("aaaa is not a pulse check"), "{}", stderr);
+}
+
+#[test]
+fn update_sends_the_new_values() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let check = cli.json(&["update", "aaaa", "--period", "300", "--alias", "renamed"]);
+
+    assert_eq!(check["period"], 300);
+    assert_eq!(cli.server.checks()[0].alias.as_deref(), Some("renamed"));
+}
+
+#[test]
+fn downtimes_prints_the_downtimes() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_downtime(
+        "aaaa",
+        Downtime {
+            id: Some("1".to_string()),
+            duration: Some(Duration::from_secs(867)),
+            ..Default::default()
+        },
+    );
+
+    let downtimes = cli.json(&["downtimes", "aaaa"]);
+
+    assert_eq!(downtimes[0]["duration"], 867);
+}
+
+#[test]
+fn table_output_aligns_the_checks() {
+    let cli = Cli::new();
+    cli.server.add_check(Check {
+        alias: Some("shop".to_string()),
+        uptime: Some(99.5),
+        down: Some(false),
+        ..example_check("aaaa")
+    });
+    cli.server.add_check(Check {
+        down: Some(true),
+        period: Some(Period::Min5),
+        ..example_check("bbbb")
+    });
+
+    let output = cli.run(&["all", "--output", "table"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "TOKEN  ALIAS  URL                       STATUS  UPTIME  PERIOD  LAST CHECK",
+            "aaaa   shop   https://aaaa.example.com  up      99.50%  1m",
+            "bbbb          https://bbbb.example.com  down            5m",
+        ]
+    );
+}
+
+#[test]
+fn table_output_lists_the_fields_of_a_check() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let output = cli.run(&["--output", "table", "check", "aaaa"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("token:   aaaa\n"), "{}", stdout);
+    assert!(stdout.contains("period:  1m\n"), "{}", stdout);
+}
+
+#[test]
+fn table_output_shows_human_downtime_durations() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_downtime(
+        "aaaa",
+        Downtime {
+            id: Some("1".to_string()),
+            error: Some("500".to_string()),
+            duration: Some(Duration::from_secs(867)),
+            ..Default::default()
+        },
+    );
+
+    let output = cli.run(&["downtimes", "aaaa", "--output", "table"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().nth(1),
+        Some("         ongoing  14m 27s   500")
+    );
+}
+
+#[test]
+fn yaml_and_csv_outputs() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let output = cli.run(&["check", "aaaa", "--output", "yaml"]);
+    let check: Check = serde_yaml::from_slice(&output.stdout).unwrap();
+    assert_eq!(check.period, Some(Period::Min1));
+
+    let output = cli.run(&["all", "--output", "csv"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "token,alias,url,status,uptime,period,last check\n\
+         aaaa,,https://aaaa.example.com,,,1m,\n"
+    );
+
+    cli.json(&["recipients", "add", "email", "oncall@example.com"]);
+    let output = cli.run(&["recipients", "list", "--output", "csv"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("id,name,type,value\n"), "{}", stdout);
+}
+
+#[test]
+fn metrics_passes_the_range_to_the_api() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    cli.json(&["metrics", "aaaa", "--from", "2020-10-01", "--group", "time"]);
+
+    let request = cli.server.requests().pop().unwrap();
+    assert_eq!(request.path, "/api/checks/aaaa/metrics");
+    assert_eq!(request.query["from"], "2020-10-01T00:00:00Z");
+    assert_eq!(request.query["group"], "time");
+}
+
+#[test]
+fn nodes_prints_plain_ip_lists() {
+    let cli = Cli::new();
+    cli.server.add_node(
+        "lan",
+        Node {
+            ip: Some("91.121.222.175".to_string()),
+            ip6: Some("2001:41d0:2:85af::1".to_string()),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(cli.json(&["nodes"])["lan"]["ip"], "91.121.222.175");
+    assert_eq!(cli.json(&["nodes", "--ipv6"])[0], "2001:41d0:2:85af::1");
+    let output = cli.run(&["nodes", "--plain"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "91.121.222.175\n2001:41d0:2:85af::1\n"
+    );
+    let output = cli.run(&["nodes", "--ipv4", "--plain"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "91.121.222.175\n");
+}
+
+#[test]
+fn recipients_are_managed_through_subcommands() {
+    let cli = Cli::new();
+
+    let recipient = cli.json(&[
+        "recipients",
+        "add",
+        "email",
+        "oncall@example.com",
+        "--name",
+        "On call",
+    ]);
+    let id = recipient["id"].as_str().unwrap();
+    assert_eq!(recipient["type"], "email");
+    assert_eq!(
+        cli.json(&["recipients", "list"])[0]["value"],
+        "oncall@example.com"
+    );
+
+    cli.json(&["add", "https://example.com", "--recipient", id]);
+    assert_eq!(
+        cli.server.checks()[0].recipients,
+        Some(vec![id.to_string()])
+    );
+
+    assert_eq!(cli.json(&["recipients", "delete", id])["deleted"], true);
+    assert!(cli.server.recipients().is_empty());
+}
+
+#[test]
+fn status_pages_reference_checks_by_token_or_alias() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.server.add_check(Check {
+        alias: Some("api".to_string()),
+        ..example_check("bbbb")
+    });
+
+    let page = cli.json(&[
+        "status-pages",
+        "add",
+        "--name",
+        "Public status",
+        "--check",
+        "aaaa",
+        "--check",
+        "api",
+    ]);
+    assert_eq!(page["checks"], serde_json::json!(["aaaa", "bbbb"]));
+
+    let token = page["token"].as_str().unwrap();
+    let page = cli.json(&["status-pages", "update", token, "--check", "api"]);
+    assert_eq!(page["checks"], serde_json::json!(["bbbb"]));
+    assert_eq!(
+        cli.json(&["status-pages", "list"])[0]["name"],
+        "Public status"
+    );
+    assert_eq!(
+        cli.json(&["status-pages", "delete", token])["deleted"],
+        true
+    );
+}
+
+#[test]
+fn status_page_with_unknown_check_is_refused() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let (code, stderr) = cli.fail(&["status-pages", "add", "--check", "nope"]);
+
+    assert_eq!(code, exitcode::USAGE);
+    assert!(
+        stderr.contains("no check has the token, alias or URL nope"),
+        "{}",
+        stderr
+    );
+    assert!(cli.server.status_pages().is_empty());
+}
+
+#[test]
+fn webhooks_are_managed_through_subcommands() {
+    let cli = Cli::new();
+
+    let webhook = cli.json(&["webhooks", "add", "https://hooks.example.com/updown"]);
+    let id = webhook["id"].as_str().unwrap();
+    assert_eq!(
+        cli.json(&["webhooks", "list"])[0]["url"],
+        "https://hooks.example.com/updown"
+    );
+    assert_eq!(cli.json(&["webhooks", "delete", id])["deleted"], true);
+    assert!(cli.server.webhooks().is_empty());
+}
+
+#[test]
+fn invalid_webhook_url_is_reported_as_bad_input() {
+    let cli = Cli::new();
+
+    let (code, stderr) = cli.fail(&["webhooks", "add", "not a url"]);
+
+    assert_eq!(code, exitcode::DATAERR);
+    assert!(stderr.contains("url: must be a valid URL"), "{}", stderr);
+    assert!(cli.server.requests().is_empty());
+}
+
+#[test]
+fn mute_until_accepts_forever_and_relative_times() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    cli.json(&["update", "aaaa", "--mute-until", "forever"]);
+    let request = cli.server.requests().pop().unwrap();
+    assert!(
+        request.body.contains(r#""mute_until":"forever""#),
+        "{}",
+        request.body
+    );
+
+    cli.json(&["update", "aaaa", "--mute-until", "+2h"]);
+    match cli.server.checks()[0].mute_until {
+        Some(MuteUntil::Time(time)) => {
+            let from_now = time - Utc::now();
+            assert!(from_now > chrono::Duration::minutes(119), "{}", from_now);
+            assert!(from_now <= chrono::Duration::hours(2), "{}", from_now);
+        }
+        other => panic!("Unexpected mute_until {:?}", other),
+    }
+}
+
+#[test]
+fn metrics_range_must_be_in_order() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let (code, stderr) = cli.fail(&["metrics", "aaaa", "--from", "now", "--to", "-7d"]);
+
+    assert_eq!(code, exitcode::USAGE);
+    assert!(stderr.contains("is after --to"), "{}", stderr);
+    assert!(cli.server.requests().is_empty());
+}
+
+#[test]
+fn invalid_period_is_reported_as_bad_input() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+
+    let (code, stderr) = cli.fail(&["update", "aaaa", "--period", "7"]);
+
+    assert_eq!(code, exitcode::DATAERR);
+    assert!(stderr.contains("period: must be one of"), "{}", stderr);
+    assert!(cli.server.requests().is_empty());
+}
+
+#[test]
+fn unparseable_value_is_reported_as_usage_error() {
+    let cli = Cli::new();
+
+    let (code, stderr) = cli.fail(&["downtimes", "aaaa", "--page", "two"]);
+
+    assert_eq!(code, exitcode::USAGE);
+    assert!(stderr.contains("invalid digit"), "{}", stderr);
+}
+
+#[test]
+fn invalid_api_key_is_reported_as_auth_error() {
+    let cli = Cli::new();
+    cli.run(&["config", "wrong-key", "", "tests"]);
+
+    let (code, stderr) = cli.fail(&["all"]);
+
+    assert_eq!(code, exitcode::NOPERM);
+    assert!(stderr.contains("Invalid API key"), "{}", stderr);
+}
+
+#[test]
+fn unknown_token_is_reported_as_api_error() {
+    let cli = Cli::new();
+
+    let (code, stderr) = cli.fail(&["check", "nope"]);
+
+    assert_eq!(code, exitcode::PROTOCOL);
+    assert!(
+        stderr.contains("Check not found (404 not found)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn unreachable_server_is_reported_as_network_failure() {
+    let cli = Cli::new();
+
+    let output = cli
+        .command(&["all"])
+        .env("UPDOWN_BASE_URL", "http://127.0.0.1:1")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(exitcode::UNAVAILABLE));
+}
+
+#[test]
+fn read_only_configuration_refuses_changes() {
+    let cli = Cli::new();
+    cli.server.add_check(example_check("aaaa"));
+    cli.run(&["config", "", MockServer::READ_ONLY_API_KEY, "tests"]);
+
+    assert_eq!(cli.json(&["all"]).as_array().unwrap().len(), 1);
+    let (code, stderr) = cli.fail(&["delete", "aaaa"]);
+
+    assert_eq!(code, exitcode::NOPERM);
+    assert!(stderr.contains("full API key"), "{}", stderr);
+    assert_eq!(cli.server.checks().len(), 1);
+}
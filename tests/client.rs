@@ -0,0 +1,587 @@
+use chrono::{TimeZone, Utc};
+use std::time::Duration;
+use updown::client::ClientBuilder;
+use updown::messages::check::{Check, CheckParamsBuilder, CheckType, HttpVerb, MuteUntil, Period};
+use updown::messages::downtime::{Downtime, DowntimeParamsBuilder};
+use updown::messages::metric::{Message, Metrics, MetricsParamsBuilder};
+use updown::messages::node::Node;
+use updown::messages::pulse::PulseParamsBuilder;
+use updown::messages::recipient::{RecipientParamsBuilder, RecipientType};
+use updown::messages::status_page::{StatusPage, StatusPageParamsBuilder, Visibility};
+use updown::messages::webhook::{Webhook, WebhookParamsBuilder};
+use updown::messages::Deleted;
+use updown::retry::RetryPolicy;
+use updown::testing::MockServer;
+use updown::{ApiError, Client, UpdownError};
+
+fn client(server: &MockServer) -> Client {
+    ClientBuilder::default()
+        .api_key(MockServer::API_KEY)
+        .base_url(server.url())
+        .build()
+        .unwrap()
+}
+
+fn api_error<T: std::fmt::Debug>(result: Result<T, UpdownError>) -> ApiError {
+    match result {
+        Err(UpdownError::ApiFailed(e)) => e,
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+}
+
+fn example_check(token: &str) -> Check {
+    Check {
+        token: Some(token.to_string()),
+        url: Some(format!("https://{}.example.com", token)),
+        alias: Some(format!("{} alias", token)),
+        period: Some(Period::Min1),
+        down: Some(false),
+        uptime: Some(99.9),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn all_returns_every_check() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    server.add_check(example_check("bbbb"));
+
+    let checks = client(&server).all().await.unwrap();
+
+    let tokens: Vec<_> = checks.iter().map(|c| c.token.clone().unwrap()).collect();
+    assert_eq!(tokens, vec!["aaaa", "bbbb"]);
+    assert_eq!(checks[0].uptime, Some(99.9));
+}
+
+#[tokio::test]
+async fn invalid_api_key_is_unauthorized() {
+    let server = MockServer::start();
+    let client = ClientBuilder::default()
+        .api_key("wrong-key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let error = api_error(client.all().await);
+
+    assert!(error.is_unauthorized());
+    assert_eq!(error.message, "Invalid API key");
+}
+
+#[tokio::test]
+async fn check_includes_metrics_when_asked() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    server.set_metrics(
+        "aaaa",
+        Message {
+            apdex: Some(0.98),
+            requests: None,
+            timings: None,
+        },
+    );
+    let client = client(&server);
+
+    let check = client.check("aaaa", false).await.unwrap();
+    assert_eq!(check.alias.as_deref(), Some("aaaa alias"));
+    assert!(check.metrics.is_none());
+
+    let check = client.check("aaaa", true).await.unwrap();
+    assert_eq!(check.metrics.unwrap().apdex, Some(0.98));
+}
+
+#[tokio::test]
+async fn create_adds_a_check() {
+    let server = MockServer::start();
+    let client = client(&server);
+
+    let params = CheckParamsBuilder::default()
+        .url("https://new.example.com".to_string())
+        .period(Period::Min5)
+        .build()
+        .unwrap();
+    let check = client.create(&params).await.unwrap();
+
+    assert!(check.token.is_some());
+    assert_eq!(check.period, Some(Period::Min5));
+    assert_eq!(server.checks()[0].token, check.token);
+    let request = server.requests().pop().unwrap();
+    assert_eq!(
+        (request.method.as_str(), request.path.as_str()),
+        ("POST", "/api/checks")
+    );
+}
+
+#[tokio::test]
+async fn pulse_checks_are_created_and_pinged() {
+    let server = MockServer::start();
+    let client = client(&server);
+    let params = CheckParamsBuilder::default()
+        .check_type(CheckType::Pulse)
+        .alias("nightly backup".to_string())
+        .build()
+        .unwrap();
+
+    let check = client.create(&params).await.unwrap();
+    assert_eq!(check.check_type, Some(CheckType::Pulse));
+    let pulse_url = check.pulse_url.unwrap();
+
+    let report = PulseParamsBuilder::default()
+        .exit_code(0)
+        .duration(12.5)
+        .build()
+        .unwrap();
+    client.pulse(&pulse_url, &report).await.unwrap();
+
+    let request = server.requests().pop().unwrap();
+    assert_eq!(request.path, format!("/pulse/{}", check.token.unwrap()));
+    assert!(!request.headers.contains_key("x-api-key"));
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body, serde_json::json!({"exit_code": 0, "duration": 12.5}));
+}
+
+#[tokio::test]
+async fn checks_without_a_type_deserialize() {
+    let check: Check =
+        serde_json::from_str(r#"{"token": "aaaa", "url": "https://example.com"}"#).unwrap();
+    assert_eq!(check.check_type, None);
+
+    let check: Check = serde_json::from_str(r#"{"token": "aaaa", "type": "dns"}"#).unwrap();
+    assert_eq!(check.check_type, Some(CheckType::Other("dns".to_string())));
+    assert_eq!(serde_json::to_value(check.check_type).unwrap(), "dns");
+}
+
+#[test]
+fn unknown_periods_and_verbs_deserialize() {
+    let check: Check =
+        serde_json::from_str(r#"{"token": "aaaa", "period": 45, "http_verb": "TRACE"}"#).unwrap();
+
+    assert_eq!(check.period, Some(Period::Other(45)));
+    assert_eq!(check.http_verb, Some(HttpVerb::Other("TRACE".to_string())));
+    assert_eq!(serde_json::to_value(check.period).unwrap(), 45);
+    assert_eq!(serde_json::to_value(check.http_verb).unwrap(), "TRACE");
+}
+
+#[test]
+fn typed_fields_are_read_from_the_api_format() {
+    let check: Check = serde_json::from_str(
+        r#"{"token": "aaaa", "period": 30, "http_verb": "GET/HEAD",
+            "down_since": "2020-10-30T17:28:30Z", "mute_until": "2020-11-01T00:00:00+01:00"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(check.period, Some(Period::Secs30));
+    assert_eq!(check.period.unwrap().as_duration(), Duration::from_secs(30));
+    assert_eq!(check.http_verb, Some(HttpVerb::GetHead));
+    assert_eq!(
+        check.down_since,
+        Some(Utc.with_ymd_and_hms(2020, 10, 30, 17, 28, 30).unwrap())
+    );
+    assert_eq!(
+        check.mute_until,
+        Some(MuteUntil::Time(
+            Utc.with_ymd_and_hms(2020, 10, 31, 23, 0, 0).unwrap()
+        ))
+    );
+    assert!(serde_json::from_str::<Check>(r#"{"period": "often"}"#).is_err());
+
+    let downtime: Downtime =
+        serde_json::from_str(r#"{"started_at": "2020-10-30T17:28:30Z", "duration": 867}"#).unwrap();
+    assert_eq!(downtime.duration, Some(Duration::from_secs(867)));
+    assert_eq!(downtime.ended_at, None);
+}
+
+#[tokio::test]
+async fn update_changes_the_stored_check() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    let client = client(&server);
+
+    let params = CheckParamsBuilder::default()
+        .token("aaaa".to_string())
+        .period(Period::Hour1)
+        .alias("renamed".to_string())
+        .build()
+        .unwrap();
+    let check = client.update(&params).await.unwrap();
+
+    assert_eq!(check.period, Some(Period::Hour1));
+    assert_eq!(server.checks()[0].alias.as_deref(), Some("renamed"));
+}
+
+#[tokio::test]
+async fn downtimes_are_paginated() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    for i in 0..150 {
+        server.add_downtime(
+            "aaaa",
+            Downtime {
+                id: Some(i.to_string()),
+                error: Some("Service Unavailable".to_string()),
+                duration: Some(Duration::from_secs(60)),
+                ..Default::default()
+            },
+        );
+    }
+    let client = client(&server);
+
+    let params = DowntimeParamsBuilder::default()
+        .token("aaaa")
+        .page(2)
+        .build()
+        .unwrap();
+    let downtimes = client.downtimes(&params).await.unwrap();
+
+    assert_eq!(downtimes.len(), 50);
+    assert_eq!(downtimes[0].id.as_deref(), Some("100"));
+}
+
+#[tokio::test]
+async fn downtimes_for_unknown_token_is_not_found() {
+    let server = MockServer::start();
+    let client = client(&server);
+
+    let params = DowntimeParamsBuilder::default()
+        .token("nope")
+        .build()
+        .unwrap();
+    let error = api_error(client.downtimes(&params).await);
+
+    assert!(error.is_not_found());
+    assert_eq!(error.message, "Check not found");
+}
+
+#[tokio::test]
+async fn metrics_are_requested_for_the_given_token() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    server.set_metrics(
+        "aaaa",
+        Message {
+            apdex: Some(0.5),
+            requests: None,
+            timings: None,
+        },
+    );
+    let client = client(&server);
+
+    let params = MetricsParamsBuilder::default()
+        .token("aaaa")
+        .from(Utc.with_ymd_and_hms(2020, 10, 1, 0, 0, 0).unwrap())
+        .build()
+        .unwrap();
+    match client.metrics(&params).await.unwrap() {
+        Metrics::Metrics(Some(message)) => assert_eq!(message.apdex, Some(0.5)),
+        other => panic!("Unexpected response {:?}", other),
+    }
+
+    let request = server.requests().pop().unwrap();
+    assert_eq!(request.path, "/api/checks/aaaa/metrics");
+    assert_eq!(
+        request.query.get("from").map(String::as_str),
+        Some("2020-10-01T00:00:00Z")
+    );
+}
+
+#[tokio::test]
+async fn unknown_token_is_not_found_for_every_check_method() {
+    let server = MockServer::start();
+    let client = client(&server);
+    let params = CheckParamsBuilder::default()
+        .token("nope".to_string())
+        .period(Period::Min1)
+        .build()
+        .unwrap();
+
+    assert_eq!(api_error(client.check("nope", false).await).status, 404);
+    assert_eq!(api_error(client.update(&params).await).status, 404);
+    assert_eq!(api_error(client.delete("nope").await).status, 404);
+}
+
+#[tokio::test]
+async fn tokens_cannot_reach_other_endpoints() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    let client = client(&server);
+
+    assert_eq!(
+        api_error(client.delete("../webhooks/abc").await).status,
+        404
+    );
+    assert_eq!(
+        server.requests().pop().unwrap().path,
+        "/api/checks/..%2Fwebhooks%2Fabc"
+    );
+    assert!(matches!(
+        client.delete("..").await,
+        Err(UpdownError::BadArg(_))
+    ));
+    assert_eq!(server.checks().len(), 1);
+}
+
+#[tokio::test]
+async fn delete_removes_the_check() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+
+    let deleted = client(&server).delete("aaaa").await.unwrap();
+
+    assert_eq!(deleted, Deleted { deleted: true });
+    assert!(server.checks().is_empty());
+}
+
+fn example_node(ip: &str, ip6: &str) -> Node {
+    Node {
+        ip: Some(ip.to_string()),
+        ip6: Some(ip6.to_string()),
+        city: Some("Gravelines".to_string()),
+        country_code: Some("fr".to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn nodes_are_keyed_by_location_code() {
+    let server = MockServer::start();
+    server.add_node("lan", example_node("91.121.222.175", "2001:41d0:2:85af::1"));
+    server.add_node("mia", example_node("45.32.74.41", "2001:19f0:9002:11a::1"));
+
+    let nodes = client(&server).nodes().await.unwrap();
+
+    assert_eq!(nodes.keys().collect::<Vec<_>>(), vec!["lan", "mia"]);
+    assert_eq!(nodes["lan"].city.as_deref(), Some("Gravelines"));
+}
+
+#[tokio::test]
+async fn node_ips_are_listed_by_family() {
+    let server = MockServer::start();
+    server.add_node("lan", example_node("91.121.222.175", "2001:41d0:2:85af::1"));
+    let client = client(&server);
+
+    assert_eq!(client.nodes_ipv4().await.unwrap(), vec!["91.121.222.175"]);
+    assert_eq!(
+        client.nodes_ipv6().await.unwrap(),
+        vec!["2001:41d0:2:85af::1"]
+    );
+    let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+    assert_eq!(paths, vec!["/api/nodes/ipv4", "/api/nodes/ipv6"]);
+}
+
+#[tokio::test]
+async fn recipients_are_created_listed_and_deleted() {
+    let server = MockServer::start();
+    let client = client(&server);
+    let params = RecipientParamsBuilder::default()
+        .kind(RecipientType::Slack)
+        .value("https://hooks.slack.com/services/T0/B0/X".to_string())
+        .name("on-call".to_string())
+        .build()
+        .unwrap();
+
+    let recipient = client.create_recipient(&params).await.unwrap();
+    assert_eq!(recipient.kind, RecipientType::Slack);
+    assert_eq!(client.recipients().await.unwrap(), vec![recipient.clone()]);
+    let request = server.requests().remove(0);
+    assert!(request.body.contains(r#""type":"slack_compatible""#));
+
+    let deleted = client
+        .delete_recipient(recipient.id.as_deref().unwrap())
+        .await
+        .unwrap();
+    assert!(deleted.deleted);
+    assert!(server.recipients().is_empty());
+}
+
+#[tokio::test]
+async fn checks_are_created_with_recipients() {
+    let server = MockServer::start();
+    let params = CheckParamsBuilder::default()
+        .url("https://example.com".to_string())
+        .recipients(vec!["email:1".to_string(), "sms:2".to_string()])
+        .build()
+        .unwrap();
+
+    let check = client(&server).create(&params).await.unwrap();
+
+    assert_eq!(
+        check.recipients,
+        Some(vec!["email:1".to_string(), "sms:2".to_string()])
+    );
+}
+
+#[tokio::test]
+async fn status_pages_are_created_updated_and_deleted() {
+    let server = MockServer::start();
+    let client = client(&server);
+    let params = StatusPageParamsBuilder::default()
+        .name("Public status".to_string())
+        .checks(vec!["aaaa".to_string()])
+        .build()
+        .unwrap();
+
+    let page = client.create_status_page(&params).await.unwrap();
+    assert_eq!(page.visibility, Some(Visibility::Public));
+    assert_eq!(client.status_pages().await.unwrap(), vec![page.clone()]);
+
+    let token = page.token.unwrap();
+    let params = StatusPageParamsBuilder::default()
+        .token(token.clone())
+        .visibility(Visibility::Protected)
+        .access_key("secret".to_string())
+        .build()
+        .unwrap();
+    let page = client.update_status_page(&params).await.unwrap();
+    assert_eq!(page.visibility, Some(Visibility::Protected));
+    assert_eq!(page.checks, Some(vec!["aaaa".to_string()]));
+
+    assert!(client.delete_status_page(&token).await.unwrap().deleted);
+    assert!(server.status_pages().is_empty());
+}
+
+#[test]
+fn unknown_visibilities_deserialize() {
+    let page: StatusPage =
+        serde_json::from_str(r#"{"token": "aaaa", "visibility": "team"}"#).unwrap();
+
+    assert_eq!(page.visibility, Some(Visibility::Other("team".to_string())));
+    assert_eq!(serde_json::to_value(page.visibility).unwrap(), "team");
+}
+
+#[tokio::test]
+async fn webhooks_are_created_listed_and_deleted() {
+    let server = MockServer::start();
+    let client = client(&server);
+    let params = WebhookParamsBuilder::default()
+        .url("https://hooks.example.com/updown".to_string())
+        .build()
+        .unwrap();
+
+    let webhook = client.create_webhook(&params).await.unwrap();
+    assert_eq!(client.webhooks().await.unwrap(), vec![webhook.clone()]);
+
+    let deleted = client
+        .delete_webhook(webhook.id.as_deref().unwrap())
+        .await
+        .unwrap();
+    assert!(deleted.deleted);
+    assert!(server.webhooks().is_empty());
+}
+
+#[tokio::test]
+async fn unknown_webhook_is_not_found() {
+    let server = MockServer::start();
+    server.add_webhook(Webhook {
+        url: Some("https://hooks.example.com".to_string()),
+        ..Default::default()
+    });
+
+    let error = api_error(client(&server).delete_webhook("nope").await);
+
+    assert!(error.is_not_found());
+    assert_eq!(server.webhooks().len(), 1);
+}
+
+#[tokio::test]
+async fn rate_limiting_and_server_errors_are_typed() {
+    let server = MockServer::start();
+    server.fail_next(429, 1);
+    server.fail_next(503, 1);
+    let client = ClientBuilder::default()
+        .api_key(MockServer::API_KEY)
+        .base_url(server.url())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let error = api_error(client.all().await);
+    assert!(error.is_rate_limited());
+    assert_eq!(error.message, "Too Many Requests");
+
+    let error = api_error(client.all().await);
+    assert!(error.is_server_error());
+    assert_eq!(error.status, 503);
+
+    assert!(client.all().await.is_ok());
+}
+
+#[tokio::test]
+async fn get_requests_use_the_read_only_key_and_changes_the_full_key() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    let client = ClientBuilder::default()
+        .api_key(MockServer::API_KEY)
+        .read_only_api_key(MockServer::READ_ONLY_API_KEY)
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client.all().await.unwrap();
+    client.check("aaaa", false).await.unwrap();
+    client.delete("aaaa").await.unwrap();
+
+    let keys: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|r| (r.method.clone(), r.headers["x-api-key"].clone()))
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            ("GET".to_string(), MockServer::READ_ONLY_API_KEY.to_string()),
+            ("GET".to_string(), MockServer::READ_ONLY_API_KEY.to_string()),
+            ("DELETE".to_string(), MockServer::API_KEY.to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn read_only_client_refuses_changes() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    let client = ClientBuilder::default()
+        .read_only_api_key(MockServer::READ_ONLY_API_KEY)
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    assert_eq!(client.all().await.unwrap().len(), 1);
+    match client.delete("aaaa").await {
+        Err(UpdownError::Unauthorized(message)) => assert!(message.contains("full API key")),
+        other => panic!("Expected the delete to be refused, got {:?}", other),
+    }
+    assert_eq!(server.requests().len(), 1);
+    assert_eq!(server.checks().len(), 1);
+}
+
+#[tokio::test]
+async fn requests_carry_the_user_agent_and_keep_the_key_out_of_the_url() {
+    let server = MockServer::start();
+    server.add_check(example_check("aaaa"));
+    let client = ClientBuilder::default()
+        .api_key(MockServer::API_KEY)
+        .user_agent("my-agent/1.0")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client.all().await.unwrap();
+    client.check("aaaa", true).await.unwrap();
+
+    for request in server.requests() {
+        assert_eq!(request.headers["user-agent"], "my-agent/1.0");
+        assert_eq!(request.headers["x-api-key"], MockServer::API_KEY);
+        assert!(!request.query.contains_key("api-key"));
+    }
+}
+
+#[tokio::test]
+async fn default_user_agent_is_sent_when_none_is_configured() {
+    let server = MockServer::start();
+
+    client(&server).all().await.unwrap();
+
+    let request = server.requests().pop().unwrap();
+    assert!(request.headers["user-agent"].starts_with("updown-rust/"));
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use updown::manifest::{Action, CheckSpec, Manifest};
+use updown::messages::check::{Check, CheckType, Period};
+
+fn live(token: &str, alias: Option<&str>, url: &str) -> Check {
+    Check {
+        token: Some(token.to_string()),
+        alias: alias.map(str::to_string),
+        url: Some(url.to_string()),
+        period: Some(Period::Min1),
+        enabled: Some(true),
+        ..Default::default()
+    }
+}
+
+fn spec(url: &str) -> CheckSpec {
+    CheckSpec {
+        url: Some(url.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn plan_creates_updates_and_prunes() {
+    let checks = vec![
+        live("aaaa", Some("shop"), "https://old.example.com"),
+        live("bbbb", None, "https://blog.example.com/"),
+        live("cccc", None, "https://gone.example.com"),
+    ];
+    let manifest = Manifest {
+        checks: vec![
+            CheckSpec {
+                alias: Some("shop".to_string()),
+                ..spec("https://shop.example.com")
+            },
+            CheckSpec {
+                period: Some(Period::Min1),
+                ..spec("https://blog.example.com")
+            },
+            CheckSpec {
+                period: Some(Period::Min5),
+                ..spec("https://new.example.com")
+            },
+        ],
+    };
+
+    let changes = manifest.plan(&checks, false).unwrap();
+
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].action, Action::Update);
+    assert_eq!(changes[0].token.as_deref(), Some("aaaa"));
+    assert_eq!(changes[0].diff[0].field, "url");
+    assert_eq!(changes[0].diff[0].old, "https://old.example.com");
+    assert_eq!(
+        changes[0].params.url.as_deref(),
+        Some("https://shop.example.com")
+    );
+    assert_eq!(changes[0].params.alias, None);
+    assert_eq!(changes[1].action, Action::Create);
+    assert_eq!(changes[1].params.period, Some(Period::Min5));
+
+    let changes = manifest.plan(&checks, true).unwrap();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[2].action, Action::Delete);
+    assert_eq!(changes[2].token.as_deref(), Some("cccc"));
+}
+
+#[test]
+fn unordered_locations_and_missing_headers_are_no_change() {
+    let checks = vec![Check {
+        disabled_locations: Some(vec!["syd".to_string(), "lan".to_string()]),
+        custom_headers: Some(HashMap::new()),
+        ..live("aaaa", None, "https://shop.example.com")
+    }];
+    let manifest = Manifest {
+        checks: vec![CheckSpec {
+            disabled_locations: Some(vec!["lan".to_string(), "syd".to_string()]),
+            headers: Some(Default::default()),
+            string_match: Some(String::new()),
+            ..spec("https://shop.example.com")
+        }],
+    };
+
+    assert!(manifest.plan(&checks, false).unwrap().is_empty());
+}
+
+#[test]
+fn manifests_need_urls_and_unique_checks() {
+    let pulse = CheckSpec {
+        check_type: Some(CheckType::Pulse),
+        ..Default::default()
+    };
+    let manifests = vec![
+        Manifest {
+            checks: vec![CheckSpec::default()],
+        },
+        Manifest {
+            checks: vec![pulse],
+        },
+        Manifest {
+            checks: vec![
+                spec("https://a.example.com"),
+                spec("https://a.example.com/"),
+            ],
+        },
+        Manifest {
+            checks: vec![spec("not a url")],
+        },
+        Manifest {
+            checks: vec![CheckSpec {
+                period: Some(Period::Other(45)),
+                ..spec("https://a.example.com")
+            }],
+        },
+    ];
+
+    for manifest in manifests {
+        assert!(manifest.validate().is_err(), "{:?}", manifest);
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant};
+
+use updown::client::ClientBuilder;
+use updown::rate_limit::{RateLimiter, MIN_REQUESTS_PER_SECOND};
+use updown::testing::MockServer;
+use updown::{Client, Config};
+
+fn client(server: &MockServer, rate_limiter: RateLimiter) -> Client {
+    ClientBuilder::default()
+        .api_key(MockServer::API_KEY)
+        .base_url(server.url())
+        .rate_limiter(rate_limiter)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn burst_is_not_delayed() {
+    let limiter = RateLimiter::new(1.0, 5).unwrap();
+
+    let start = Instant::now();
+    for _ in 0..5 {
+        limiter.acquire().await;
+    }
+
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn requests_beyond_the_burst_wait_for_the_rate() {
+    let limiter = RateLimiter::new(20.0, 1).unwrap();
+
+    let start = Instant::now();
+    for _ in 0..5 {
+        limiter.acquire().await;
+    }
+
+    // The first request uses the burst, the next four wait 50ms each
+    assert!(start.elapsed() >= Duration::from_millis(190));
+}
+
+#[tokio::test]
+async fn spawned_tasks_share_one_budget() {
+    let server = MockServer::start();
+    let client = client(&server, RateLimiter::new(20.0, 2).unwrap());
+
+    let start = Instant::now();
+    let tasks: Vec<_> = (0..6)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.all().await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+
+    // Two requests go out at once, the other four are spaced 50ms apart
+    assert!(start.elapsed() >= Duration::from_millis(190));
+    assert_eq!(server.requests().len(), 6);
+}
+
+#[test]
+fn config_without_a_rate_is_not_limited() {
+    let config = Config {
+        api_key: "key".to_string(),
+        ..Default::default()
+    };
+
+    assert!(config.rate_limiter().unwrap().is_none());
+}
+
+#[tokio::test]
+async fn config_burst_defaults_to_one_second_of_requests() {
+    let config = Config {
+        api_key: "key".to_string(),
+        requests_per_second: Some(3.0),
+        ..Default::default()
+    };
+    let limiter = config.rate_limiter().unwrap().unwrap();
+
+    let start = Instant::now();
+    for _ in 0..3 {
+        limiter.acquire().await;
+    }
+    assert!(start.elapsed() < Duration::from_millis(200));
+    limiter.acquire().await;
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
+
+#[test]
+fn unusable_rates_are_rejected() {
+    for rate in &[0.0, -1.0, 0.000_1, f64::NAN, f64::INFINITY] {
+        assert!(RateLimiter::new(*rate, 1).is_err(), "{} was accepted", rate);
+    }
+
+    let config = Config {
+        api_key: "key".to_string(),
+        requests_per_second: Some(f64::NAN),
+        ..Default::default()
+    };
+    assert!(Client::from_config(&config).is_err());
+}
+
+#[tokio::test]
+async fn the_slowest_rate_does_not_overflow_the_wait() {
+    let limiter = RateLimiter::new(MIN_REQUESTS_PER_SECOND, 1).unwrap();
+
+    limiter.acquire().await;
+    let second = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+
+    assert!(second.is_err(), "the second request did not wait");
+}
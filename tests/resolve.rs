@@ -0,0 +1,45 @@
+use updown::messages::check::Check;
+use updown::resolve::{find, glob};
+
+fn check(token: &str, alias: Option<&str>, url: &str) -> Check {
+    Check {
+        token: Some(token.to_string()),
+        alias: alias.map(str::to_string),
+        url: Some(url.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn glob_matches_wildcards() {
+    assert!(glob(
+        "https://*.example.com/*",
+        "https://shop.example.com/cart"
+    ));
+    assert!(glob("shop-??", "shop-eu"));
+    assert!(glob("*", ""));
+    assert!(glob("a*b*c", "axxbyybc"));
+    assert!(!glob("shop-??", "shop-e"));
+    assert!(!glob("https://*.example.com", "https://example.org"));
+}
+
+#[test]
+fn tokens_come_before_aliases_and_urls() {
+    let checks = vec![
+        check("aaaa", Some("bbbb"), "https://a.example.com"),
+        check("bbbb", None, "https://b.example.com/"),
+    ];
+
+    let token = |reference| {
+        find(&checks, reference)
+            .unwrap()
+            .and_then(|c| c.token.clone())
+    };
+
+    assert_eq!(token("bbbb").as_deref(), Some("bbbb"));
+    assert_eq!(token("https://a.example.com/").as_deref(), Some("aaaa"));
+    assert_eq!(token("https://b.example.com").as_deref(), Some("bbbb"));
+    assert_eq!(token("https://a.*").as_deref(), Some("aaaa"));
+    assert_eq!(token("cccc"), None);
+    assert!(find(&checks, "https://*").is_err());
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+use updown::client::ClientBuilder;
+use updown::messages::check::Check;
+use updown::retry::{RetryPolicy, RetryPolicyBuilder};
+use updown::testing::MockServer;
+use updown::{Client, UpdownError};
+
+fn client(server: &MockServer, policy: RetryPolicy) -> Client {
+    ClientBuilder::default()
+        .api_key(MockServer::API_KEY)
+        .base_url(server.url())
+        .retry_policy(policy)
+        .build()
+        .unwrap()
+}
+
+fn fast_policy() -> RetryPolicyBuilder {
+    let mut policy = RetryPolicyBuilder::default();
+    policy
+        .max_attempts(3u32)
+        .base_delay(Duration::from_millis(10))
+        .jitter(0.0);
+    policy
+}
+
+fn status<T: std::fmt::Debug>(result: Result<T, UpdownError>) -> u16 {
+    match result {
+        Err(UpdownError::ApiFailed(e)) => e.status,
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn get_is_retried_until_it_succeeds() {
+    let server = MockServer::start();
+    server.fail_next(503, 1);
+    server.fail_next(429, 1);
+
+    let checks = client(&server, fast_policy().build().unwrap())
+        .all()
+        .await
+        .unwrap();
+
+    assert!(checks.is_empty());
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn retries_stop_after_max_attempts() {
+    let server = MockServer::start();
+    server.fail_next(502, 5);
+
+    let result = client(&server, fast_policy().build().unwrap()).all().await;
+
+    assert_eq!(status(result), 502);
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn statuses_that_are_not_retryable_fail_at_once() {
+    let server = MockServer::start();
+
+    let result = client(&server, fast_policy().build().unwrap())
+        .check("nope", false)
+        .await;
+
+    assert_eq!(status(result), 404);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn changes_are_not_replayed_by_default() {
+    let server = MockServer::start();
+    server.add_check(Check {
+        token: Some("aaaa".to_string()),
+        ..Default::default()
+    });
+    server.fail_next(503, 1);
+
+    let result = client(&server, fast_policy().build().unwrap())
+        .delete("aaaa")
+        .await;
+
+    assert_eq!(status(result), 503);
+    assert_eq!(server.requests().len(), 1);
+    assert_eq!(server.checks().len(), 1);
+}
+
+#[tokio::test]
+async fn changes_are_replayed_when_allowed() {
+    let server = MockServer::start();
+    server.add_check(Check {
+        token: Some("aaaa".to_string()),
+        ..Default::default()
+    });
+    server.fail_next(503, 1);
+    let policy = fast_policy().retry_non_idempotent(true).build().unwrap();
+
+    client(&server, policy).delete("aaaa").await.unwrap();
+
+    assert_eq!(server.requests().len(), 2);
+    assert!(server.checks().is_empty());
+}
+
+#[tokio::test]
+async fn retry_after_is_honoured() {
+    let server = MockServer::start();
+    server.fail_next_with_retry_after(429, 1, 1);
+
+    let start = Instant::now();
+    client(&server, fast_policy().build().unwrap())
+        .all()
+        .await
+        .unwrap();
+
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn retry_after_beyond_max_delay_ends_the_retries() {
+    let server = MockServer::start();
+    server.fail_next_with_retry_after(429, 1, 120);
+
+    let result = client(&server, fast_policy().build().unwrap()).all().await;
+
+    assert_eq!(status(result), 429);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn backoff_doubles_up_to_the_maximum_delay() {
+    let policy = fast_policy()
+        .max_delay(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let delays: Vec<_> = (1..=5).map(|attempt| policy.backoff(attempt)).collect();
+
+    assert_eq!(
+        delays,
+        [10, 20, 40, 50, 50]
+            .iter()
+            .map(|ms| Duration::from_millis(*ms))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn jitter_shortens_the_delay() {
+    let policy = fast_policy().jitter(1.0).build().unwrap();
+
+    for _ in 0..20 {
+        assert!(policy.backoff(2) <= Duration::from_millis(20));
+    }
+}
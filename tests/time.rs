@@ -0,0 +1,71 @@
+use chrono::{DateTime, TimeZone, Utc};
+use updown::time::Time;
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+}
+
+fn parse(s: &str) -> DateTime<Utc> {
+    Time::parse_at(s, now()).unwrap().0
+}
+
+#[test]
+fn relative_times_are_resolved_from_now() {
+    assert_eq!(parse("now"), now());
+    assert_eq!(
+        parse("-7d"),
+        Utc.with_ymd_and_hms(2026, 10, 11, 12, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("+2h"),
+        Utc.with_ymd_and_hms(2026, 10, 18, 14, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("-1h30m"),
+        Utc.with_ymd_and_hms(2026, 10, 18, 10, 30, 0).unwrap()
+    );
+    assert_eq!(
+        parse("+1w"),
+        Utc.with_ymd_and_hms(2026, 10, 25, 12, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn dates_are_in_utc_unless_a_zone_is_given() {
+    assert_eq!(
+        parse("2026-10-20"),
+        Utc.with_ymd_and_hms(2026, 10, 20, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("2026-10-20 14:00"),
+        Utc.with_ymd_and_hms(2026, 10, 20, 14, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("2026-10-20 14:00 Europe/Paris"),
+        Utc.with_ymd_and_hms(2026, 10, 20, 12, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("2026-10-20T14:00:00-04:00"),
+        Utc.with_ymd_and_hms(2026, 10, 20, 18, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn invalid_times_are_refused() {
+    for input in &[
+        "yesterday",
+        "-7",
+        "+2x",
+        "-",
+        "2026-13-01",
+        "2026-10-20 14:00 Mars/Olympus",
+    ] {
+        assert!(
+            Time::parse_at(input, now()).is_err(),
+            "{} was accepted",
+            input
+        );
+    }
+    // Skipped by the daylight saving change
+    assert!(Time::parse_at("2026-03-29 02:30 Europe/Paris", now()).is_err());
+}